@@ -1,28 +1,38 @@
 extern crate reqwest;
 extern crate inth_oauth2;
 
-use std::io;
-
 use inth_oauth2::Client;
-use inth_oauth2::provider::google::{Installed, REDIRECT_URI_OOB};
+use inth_oauth2::client::loopback::LoopbackListener;
+use inth_oauth2::client::random_state;
+use inth_oauth2::pkce::{ChallengeMethod, CodeVerifier};
+use inth_oauth2::provider::google::Installed;
 
 fn main() {
     let http_client = reqwest::Client::new();
 
+    let listener = LoopbackListener::bind().unwrap();
+
     let client = Client::new(
         Installed,
         String::from("143225766783-ip2d9qv6sdr37276t77luk6f7bhd6bj5.apps.googleusercontent.com"),
         String::from("3kZ5WomzHFlN2f_XbhkyPd3o"),
-        Some(String::from(REDIRECT_URI_OOB)),
+        Some(String::from(listener.redirect_uri())),
     );
 
-    let auth_uri = client.auth_uri(Some("https://www.googleapis.com/auth/userinfo.email"), None);
+    let state = random_state().unwrap();
+    let verifier = CodeVerifier::new();
+    let auth_uri = client.auth_uri_with_challenge(
+        Some("https://www.googleapis.com/auth/userinfo.email"),
+        Some(&state),
+        &verifier.challenge(ChallengeMethod::S256),
+    );
     println!("{}", auth_uri);
 
-    let mut code = String::new();
-    io::stdin().read_line(&mut code).unwrap();
+    let code = listener.accept(&client, &state).unwrap();
 
-    let token = client.request_token(&http_client, code.trim()).unwrap();
+    let token = client
+        .request_token_with_verifier(&http_client, &code, &verifier)
+        .unwrap();
     println!("{:?}", token);
 
     let token = client.refresh_token(&http_client, token, None).unwrap();
@@ -0,0 +1,156 @@
+//! JWT assertion grants.
+//!
+//! See [RFC 7523, section 2.1](https://tools.ietf.org/html/rfc7523#section-2.1).
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Map, Value};
+
+use client::ClientError;
+use jwt::SigningKey;
+
+/// `grant_type` of JWT assertion grants.
+///
+/// See [RFC 7523, section 2.1](https://tools.ietf.org/html/rfc7523#section-2.1).
+pub const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// Builder for the claim set of a JWT assertion.
+///
+/// The `iat` and `exp` claims are set when the assertion is signed.
+///
+/// See [RFC 7523, section 3](https://tools.ietf.org/html/rfc7523#section-3).
+///
+/// # Examples
+///
+/// ```no_run
+/// use inth_oauth2::client::assertion::Assertion;
+/// use inth_oauth2::jwt::SigningKey;
+///
+/// let pem = std::fs::read("key.pem").unwrap();
+/// let key = SigningKey::from_pem(&pem).unwrap();
+/// let assertion = Assertion::new("issuer", "https://example.com/token")
+///     .subject("user@example.com")
+///     .scope("read write")
+///     .sign(&key)
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    iss: String,
+    aud: String,
+    sub: Option<String>,
+    scope: Option<String>,
+    lifetime: Duration,
+    claims: Map<String, Value>,
+}
+
+impl Assertion {
+    /// Creates an assertion issued by `iss` for the audience `aud`, usually the token endpoint.
+    pub fn new(iss: &str, aud: &str) -> Self {
+        Assertion {
+            iss: iss.into(),
+            aud: aud.into(),
+            sub: None,
+            scope: None,
+            lifetime: Duration::hours(1),
+            claims: Map::new(),
+        }
+    }
+
+    /// Sets the subject (`sub`), on whose behalf access is requested.
+    pub fn subject(mut self, sub: &str) -> Self {
+        self.sub = Some(sub.into());
+        self
+    }
+
+    /// Sets the requested scope, for providers expecting it in the assertion rather than the
+    /// request.
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Sets how long the assertion is valid. Defaults to one hour.
+    pub fn lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Adds another claim.
+    pub fn claim(mut self, name: &str, value: Value) -> Self {
+        self.claims.insert(name.into(), value);
+        self
+    }
+
+    /// Returns the claim set, issued at a time.
+    pub fn claims(&self, iat: DateTime<Utc>) -> Value {
+        let mut claims = self.claims.clone();
+        claims.insert(String::from("iss"), Value::from(&self.iss[..]));
+        claims.insert(String::from("aud"), Value::from(&self.aud[..]));
+        if let Some(ref sub) = self.sub {
+            claims.insert(String::from("sub"), Value::from(&sub[..]));
+        }
+        if let Some(ref scope) = self.scope {
+            claims.insert(String::from("scope"), Value::from(&scope[..]));
+        }
+        claims.insert(String::from("iat"), Value::from(iat.timestamp()));
+        claims.insert(String::from("exp"), Value::from((iat + self.lifetime).timestamp()));
+        Value::Object(claims)
+    }
+
+    /// Signs the assertion, issued now.
+    pub fn sign(&self, key: &SigningKey) -> Result<String, ClientError> {
+        Ok(key.sign(&self.claims(Utc::now()))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use jwt::tests::{jwk, rsa_key};
+    use jwt::{Jwks, Jwt, SigningKey};
+    use super::Assertion;
+
+    #[test]
+    fn claims() {
+        let iat = Utc.timestamp_opt(1_500_000_000, 0).unwrap();
+        let assertion = Assertion::new("foo", "https://example.com/token")
+            .subject("bar")
+            .scope("a b")
+            .lifetime(Duration::minutes(10))
+            .claim("azp", json!("baz"));
+        assert_eq!(
+            json!({
+                "iss": "foo",
+                "aud": "https://example.com/token",
+                "sub": "bar",
+                "scope": "a b",
+                "azp": "baz",
+                "iat": 1_500_000_000,
+                "exp": 1_500_000_600,
+            }),
+            assertion.claims(iat)
+        );
+    }
+
+    #[test]
+    fn claims_minimal() {
+        let iat = Utc.timestamp_opt(1_500_000_000, 0).unwrap();
+        let claims = Assertion::new("foo", "bar").claims(iat);
+        assert_eq!(None, claims.get("sub"));
+        assert_eq!(None, claims.get("scope"));
+        assert_eq!(Some(&json!(1_500_003_600)), claims.get("exp"));
+    }
+
+    #[test]
+    fn sign() {
+        let key = rsa_key();
+        let assertion = Assertion::new("foo", "bar")
+            .sign(&SigningKey::from_private_key(key.clone()).unwrap().kid(String::from("1")))
+            .unwrap();
+        let jwt = Jwt::decode(&assertion).unwrap();
+        assert_eq!("RS256", jwt.header().alg);
+        jwt.verify(&Jwks { keys: vec![jwk(&key, "1")] }).unwrap();
+        assert_eq!(Some(&json!("foo")), jwt.claims().get("iss"));
+    }
+}
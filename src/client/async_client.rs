@@ -0,0 +1,652 @@
+use std::io;
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Utc;
+use futures::future::{self, Either, Loop};
+use futures::{Future, Stream};
+use reqwest::async::{Client as HttpClient, Response};
+use reqwest;
+use serde_json::{self, Value};
+use tokio_timer::Delay;
+use url::form_urlencoded::Serializer;
+
+use client::device::DeviceAuthorization;
+use client::exchange::{ExchangedToken, TokenExchange};
+use client::introspection::Introspection;
+use client::par::PushedAuthorization;
+use client::response::FromResponse;
+use client::http::{HttpRequest, Method};
+use client::{Client, ClientError, check_response, device_expired, next_interval, revocation_error};
+use pkce::{CodeChallenge, CodeVerifier};
+use provider::Provider;
+use scope::Scope;
+use token::{AuthorizationDetail, Lifetime, Refresh, Token, TokenTypeHint};
+
+/// OAuth 2.0 client using the asynchronous `reqwest` client.
+///
+/// Requests are built and responses parsed exactly as by `Client`, but are sent with a
+/// `reqwest::async::Client` and returned as futures. The futures do not borrow the client, and
+/// must be run on a tokio runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate futures;
+/// # extern crate inth_oauth2;
+/// # extern crate reqwest;
+/// # extern crate tokio;
+/// use futures::Future;
+/// use inth_oauth2::{AsyncClient, Token};
+/// use inth_oauth2::provider::google::Web;
+///
+/// # fn main() {
+/// let client = AsyncClient::new(
+///     Web,
+///     String::from("client_id"),
+///     String::from("client_secret"),
+///     Some(String::from("redirect_uri")),
+/// );
+///
+/// let http = reqwest::async::Client::new();
+/// let future = client.request_token(&http, "code")
+///     .map(|token| println!("{}", token.access_token()))
+///     .map_err(|err| eprintln!("{}", err));
+/// tokio::run(future);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsyncClient<P> {
+    client: Arc<Client<P>>,
+}
+
+impl<P> From<Client<P>> for AsyncClient<P> {
+    fn from(client: Client<P>) -> Self {
+        AsyncClient { client: Arc::new(client) }
+    }
+}
+
+impl<P: Provider> AsyncClient<P> {
+    /// Creates a client.
+    ///
+    /// See `Client::new`.
+    pub fn new(
+        provider: P,
+        client_id: String,
+        client_secret: String,
+        redirect_uri: Option<String>,
+    ) -> Self {
+        AsyncClient::from(Client::new(provider, client_id, client_secret, redirect_uri))
+    }
+
+    /// Returns the underlying client, for constructing authorization URIs and parsing redirects.
+    pub fn client(&self) -> &Client<P> { &self.client }
+
+    fn post_token(
+        &self,
+        http_client: &HttpClient,
+        mut body: Serializer<String>,
+    ) -> impl Future<Item = Value, Error = ClientError> {
+        post_token(self.client.clone(), http_client.clone(), body.finish())
+    }
+
+    fn revoke(
+        &self,
+        http_client: &HttpClient,
+        token: &str,
+        hint: TokenTypeHint,
+    ) -> impl Future<Item = (), Error = ClientError> {
+        let body = self.client.revocation_body(token, hint);
+        let request = self.client.revocation_uri()
+            .and_then(|uri| self.client.form_request(uri, body));
+        let http_client = http_client.clone();
+
+        future::result(request)
+            .and_then(move |request| send_form(&http_client, request))
+            .and_then(|response| {
+                let status = response.status();
+                if status.is_success() {
+                    return Either::A(future::ok(()));
+                }
+                Either::B(response.into_body()
+                    .concat2()
+                    .map_err(ClientError::from)
+                    .and_then(move |body| Err(revocation_error(status.as_u16(), &body))))
+            })
+    }
+
+    /// Revokes an access token.
+    ///
+    /// See `Client::revoke_token`.
+    pub fn revoke_token<L: Lifetime, T: Token<L>>(
+        &self,
+        http_client: &HttpClient,
+        token: &T,
+    ) -> impl Future<Item = (), Error = ClientError> {
+        self.revoke(http_client, token.access_token(), TokenTypeHint::AccessToken)
+    }
+
+    /// Queries the provider for the state of a token.
+    ///
+    /// See `Client::introspect_token`.
+    pub fn introspect_token(
+        &self,
+        http_client: &HttpClient,
+        token: &str,
+        hint: Option<TokenTypeHint>,
+    ) -> impl Future<Item = Introspection, Error = ClientError> {
+        let body = self.client.introspection_body(token, hint);
+        let request = self.client.introspection_uri()
+            .and_then(|uri| self.client.form_request(uri, body));
+        let http_client = http_client.clone();
+
+        future::result(request)
+            .and_then(move |request| post_form(&http_client, request))
+            .and_then(|json| Ok(Introspection::from_response(&json)?))
+    }
+
+    /// Requests an access token using an authorization code.
+    ///
+    /// See `Client::request_token`.
+    pub fn request_token(
+        &self,
+        http_client: &HttpClient,
+        code: &str,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        self.request_token_with(http_client, code, None, &[])
+    }
+
+    /// Requests an access token using an authorization code and PKCE code verifier.
+    ///
+    /// See `Client::request_token_with_verifier`.
+    pub fn request_token_with_verifier(
+        &self,
+        http_client: &HttpClient,
+        code: &str,
+        verifier: &CodeVerifier,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        self.request_token_with(http_client, code, Some(verifier), &[])
+    }
+
+    /// Requests an access token using an authorization code, limited to a subset of the
+    /// authorization details granted.
+    ///
+    /// See `Client::request_token_with_details`.
+    pub fn request_token_with_details(
+        &self,
+        http_client: &HttpClient,
+        code: &str,
+        verifier: Option<&CodeVerifier>,
+        details: &[AuthorizationDetail],
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        self.request_token_with(http_client, code, verifier, details)
+    }
+
+    fn request_token_with(
+        &self,
+        http_client: &HttpClient,
+        code: &str,
+        verifier: Option<&CodeVerifier>,
+        details: &[AuthorizationDetail],
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        let body = self.client.authorization_code_body(code, verifier, details);
+        let token = self.post_token(http_client, body);
+
+        future::result(self.client.check_pkce(verifier.is_some()))
+            .and_then(move |()| token)
+            .and_then(|json| Ok(P::Token::from_response(&json)?))
+    }
+
+    /// Requests an access token using the client credentials.
+    ///
+    /// See `Client::request_client_credentials_token`.
+    pub fn request_client_credentials_token(
+        &self,
+        http_client: &HttpClient,
+        scope: Option<&str>,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        self.request_client_credentials_token_with_details(http_client, scope, &[])
+    }
+
+    /// Requests an access token using the client credentials, with authorization details.
+    ///
+    /// See `Client::request_client_credentials_token_with_details`.
+    pub fn request_client_credentials_token_with_details(
+        &self,
+        http_client: &HttpClient,
+        scope: Option<&str>,
+        details: &[AuthorizationDetail],
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        let body = self.client.client_credentials_body(scope, details);
+        self.post_token(http_client, body).and_then(|json| Ok(P::Token::from_response(&json)?))
+    }
+
+    /// Requests an access token using the resource owner password credentials.
+    ///
+    /// See `Client::request_password_token`.
+    pub fn request_password_token(
+        &self,
+        http_client: &HttpClient,
+        username: &str,
+        password: &str,
+        scope: Option<&str>,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        let body = self.client.password_body(username, password, scope);
+        self.post_token(http_client, body).and_then(|json| Ok(P::Token::from_response(&json)?))
+    }
+
+    /// Requests an access token using a signed JWT assertion.
+    ///
+    /// See `Client::request_assertion_token`.
+    pub fn request_assertion_token(
+        &self,
+        http_client: &HttpClient,
+        assertion: &str,
+        scope: Option<&str>,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        let body = self.client.assertion_body(assertion, scope);
+        self.post_token(http_client, body).and_then(|json| Ok(P::Token::from_response(&json)?))
+    }
+
+    /// Exchanges a subject token for a new token.
+    ///
+    /// See `Client::request_token_exchange`.
+    pub fn request_token_exchange(
+        &self,
+        http_client: &HttpClient,
+        exchange: &TokenExchange,
+    ) -> impl Future<Item = ExchangedToken, Error = ClientError> {
+        self.post_token(http_client, exchange.body())
+            .and_then(|json| Ok(ExchangedToken::from_response(&json)?))
+    }
+
+    /// Pushes the authorization request parameters to the provider.
+    ///
+    /// See `Client::push_authorization_request`.
+    pub fn push_authorization_request(
+        &self,
+        http_client: &HttpClient,
+        scope: Option<&str>,
+        state: Option<&str>,
+        challenge: Option<&CodeChallenge>,
+        details: &[AuthorizationDetail],
+    ) -> impl Future<Item = PushedAuthorization, Error = ClientError> {
+        let body = self.client.pushed_authorization_body(scope, state, challenge, details);
+        let request = self.client.check_pkce(challenge.is_some())
+            .and_then(|()| self.client.pushed_authorization_request_uri())
+            .and_then(|uri| self.client.form_request(uri, body));
+        let http_client = http_client.clone();
+
+        future::result(request)
+            .and_then(move |request| post_form(&http_client, request))
+            .and_then(|json| Ok(PushedAuthorization::from_response(&json)?))
+    }
+
+    /// Requests device and user verification codes.
+    ///
+    /// See `Client::request_device_code`.
+    pub fn request_device_code(
+        &self,
+        http_client: &HttpClient,
+        scope: Option<&str>,
+    ) -> impl Future<Item = DeviceAuthorization, Error = ClientError> {
+        let body = self.client.device_code_body(scope);
+        let request = self.client.device_authorization_uri()
+            .and_then(|uri| self.client.form_request(uri, body));
+        let http_client = http_client.clone();
+
+        future::result(request)
+            .and_then(move |request| post_form(&http_client, request))
+            .and_then(|json| Ok(DeviceAuthorization::from_response(&json)?))
+    }
+
+    /// Requests an access token using a device code, once.
+    ///
+    /// See `Client::request_device_token`.
+    pub fn request_device_token(
+        &self,
+        http_client: &HttpClient,
+        device: &DeviceAuthorization,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        let body = self.client.device_token_body(device);
+        self.post_token(http_client, body).and_then(|json| Ok(P::Token::from_response(&json)?))
+    }
+
+    /// Polls for an access token using a device code until the user completes authorization.
+    ///
+    /// Waits the interval requested by the provider between requests using the tokio timer.
+    ///
+    /// See `Client::poll_device_token`.
+    pub fn poll_device_token(
+        &self,
+        http_client: &HttpClient,
+        device: &DeviceAuthorization,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        let client = self.client.clone();
+        let device = device.clone();
+        let http_client = http_client.clone();
+        let expires = *device.expires();
+
+        future::loop_fn(device.interval(), move |interval| {
+            if Utc::now() >= expires {
+                return Either::A(future::err(device_expired()));
+            }
+
+            // Each poll is a new request, so client assertions and DPoP proofs are not replayed.
+            let body = client.device_token_body(&device).finish();
+            let http_client = http_client.clone();
+            let client = client.clone();
+            let poll = Delay::new(Instant::now() + interval)
+                .map_err(|err| ClientError::from(io::Error::other(err)))
+                .and_then(move |_| post_token(client, http_client, body))
+                .then(move |result| match result {
+                    Err(err) => Ok(Loop::Continue(next_interval(interval, &err).ok_or(err)?)),
+                    Ok(json) => Ok(Loop::Break(P::Token::from_response(&json)?)),
+                });
+            Either::B(poll)
+        })
+    }
+}
+
+impl<P> AsyncClient<P> where P: Provider, P::Token: Token<Refresh> {
+    /// Refreshes an access token.
+    ///
+    /// See `Client::refresh_token`.
+    pub fn refresh_token(
+        &self,
+        http_client: &HttpClient,
+        token: P::Token,
+        scope: Option<&str>,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        let body = self.client.refresh_token_body(&token, scope);
+        self.post_token(http_client, body)
+            .and_then(move |json| Ok(P::Token::from_response_inherit(&json, &token)?))
+    }
+
+    /// Refreshes an access token with a scope.
+    ///
+    /// See `Client::refresh_token_with_scope`.
+    pub fn refresh_token_with_scope(
+        &self,
+        http_client: &HttpClient,
+        token: P::Token,
+        scope: &Scope,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        self.refresh_token(http_client, token, Some(&self.client.format_scope(scope)))
+    }
+
+    /// Revokes a refresh token.
+    ///
+    /// See `Client::revoke_refresh_token`.
+    pub fn revoke_refresh_token(
+        &self,
+        http_client: &HttpClient,
+        token: &P::Token,
+    ) -> impl Future<Item = (), Error = ClientError> {
+        self.revoke(
+            http_client,
+            token.lifetime().refresh_token(),
+            TokenTypeHint::RefreshToken,
+        )
+    }
+
+    /// Ensures an access token is valid by refreshing it if necessary.
+    ///
+    /// See `Client::ensure_token`.
+    pub fn ensure_token(
+        &self,
+        http_client: &HttpClient,
+        token: P::Token,
+    ) -> impl Future<Item = P::Token, Error = ClientError> {
+        if token.lifetime().expired() {
+            Either::A(self.refresh_token(http_client, token, None))
+        } else {
+            Either::B(future::ok(token))
+        }
+    }
+}
+
+fn send_form(
+    http_client: &HttpClient,
+    request: HttpRequest,
+) -> impl Future<Item = Response, Error = ClientError> {
+    let method = match request.method {
+        Method::Get => reqwest::Method::GET,
+        Method::Post => reqwest::Method::POST,
+    };
+
+    let mut builder = http_client.request(method, request.url);
+    for (name, value) in request.headers {
+        builder = builder.header(&name[..], &value[..]);
+    }
+    builder.body(request.body).send().map_err(ClientError::from)
+}
+
+fn read_json(response: Response) -> impl Future<Item = Value, Error = ClientError> {
+    response.into_body()
+        .concat2()
+        .map_err(ClientError::from)
+        .and_then(|body| Ok(serde_json::from_slice(&body)?))
+}
+
+fn post_form(
+    http_client: &HttpClient,
+    request: HttpRequest,
+) -> impl Future<Item = Value, Error = ClientError> {
+    send_form(http_client, request)
+        .and_then(read_json)
+        .and_then(check_response)
+}
+
+/// Posts a form to the token endpoint, retrying once if the provider requires a new DPoP nonce.
+fn post_token<P: Provider>(
+    client: Arc<Client<P>>,
+    http_client: HttpClient,
+    body: String,
+) -> impl Future<Item = Value, Error = ClientError> {
+    let retry = client.clone();
+    post_token_once(&client, &http_client, body.clone())
+        .or_else(move |err| match err {
+            ClientError::OAuth2(ref err) if retry.retry_dpop_nonce(err) => {
+                Either::A(post_token_once(&retry, &http_client, body))
+            },
+            err => Either::B(future::err(err)),
+        })
+        .map(move |json| client.confirm(json))
+}
+
+fn post_token_once<P: Provider>(
+    client: &Client<P>,
+    http_client: &HttpClient,
+    body: String,
+) -> impl Future<Item = Value, Error = ClientError> {
+    let uri = client.provider.token_uri().clone();
+    let request = client.form_request(&uri, Serializer::for_suffix(body, 0));
+    let dpop = client.dpop.clone();
+    let http_client = http_client.clone();
+
+    future::result(request)
+        .and_then(move |request| send_form(&http_client, request))
+        .and_then(move |response| {
+            let nonce = response.headers().get("DPoP-Nonce").and_then(|value| value.to_str().ok());
+            if let (Some(dpop), Some(nonce)) = (dpop, nonce) {
+                dpop.set_nonce(&uri, nonce);
+            }
+            read_json(response)
+        })
+        .and_then(check_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::thread;
+
+    use futures::Future;
+    use reqwest::async::Client as HttpClient;
+    use tokio::runtime::Runtime;
+    use url::Url;
+
+    use client::ClientError;
+    use client::device::DeviceAuthorization;
+    use client::response::FromResponse;
+    use error::OAuth2ErrorCode;
+    use provider::Provider;
+    use token::{Bearer, Refresh, Token};
+    use super::AsyncClient;
+
+    struct Mock {
+        auth_uri: Url,
+        token_uri: Url,
+    }
+    impl Provider for Mock {
+        type Lifetime = Refresh;
+        type Token = Bearer<Refresh>;
+        fn auth_uri(&self) -> &Url { &self.auth_uri }
+        fn token_uri(&self) -> &Url { &self.token_uri }
+    }
+
+    /// Serves each response in turn from a local token endpoint, returning the request bodies.
+    fn serve(responses: Vec<&'static str>) -> (AsyncClient<Mock>, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind((Ipv4Addr::new(127, 0, 0, 1), 0)).unwrap();
+        let token_uri = format!("http://127.0.0.1:{}/token", listener.local_addr().unwrap().port());
+        let client = AsyncClient::new(
+            Mock {
+                auth_uri: Url::parse("http://127.0.0.1/auth").unwrap(),
+                token_uri: Url::parse(&token_uri).unwrap(),
+            },
+            String::from("foo"),
+            String::from("bar"),
+            None,
+        );
+
+        let server = thread::spawn(move || {
+            responses.into_iter().map(|response| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut length = 0;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    line.clear();
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let status = if response.contains("\"error\"") { "400 Bad Request" } else { "200 OK" };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response,
+                ).unwrap();
+                String::from_utf8(body).unwrap()
+            }).collect()
+        });
+
+        (client, server)
+    }
+
+    fn run<F: Future + Send + 'static>(future: F) -> Result<F::Item, F::Error>
+    where F::Item: Send, F::Error: Send {
+        Runtime::new().unwrap().block_on(future)
+    }
+
+    const TOKEN: &str = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":3600,"refresh_token":"bbbbbbbb"}"#;
+
+    #[test]
+    fn request_token() {
+        let (client, server) = serve(vec![TOKEN]);
+        let token = run(client.request_token(&HttpClient::new(), "code")).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!("bbbbbbbb", token.lifetime().refresh_token());
+        assert_eq!(vec!["grant_type=authorization_code&code=code"], server.join().unwrap());
+    }
+
+    #[test]
+    fn request_token_error() {
+        let (client, server) = serve(vec![r#"{"error":"invalid_grant"}"#]);
+        match run(client.request_token(&HttpClient::new(), "code")) {
+            Err(ClientError::OAuth2(ref err)) => {
+                assert_eq!(OAuth2ErrorCode::InvalidGrant, err.code);
+            },
+            result => panic!("{:?}", result),
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn request_password_token() {
+        let (client, server) = serve(vec![TOKEN]);
+        let future = client.request_password_token(&HttpClient::new(), "user", "pass", Some("a"));
+        assert_eq!("aaaaaaaa", run(future).unwrap().access_token());
+        assert_eq!(
+            vec!["grant_type=password&username=user&password=pass&scope=a"],
+            server.join().unwrap()
+        );
+    }
+
+    #[test]
+    fn ensure_token_refreshes() {
+        let (client, server) = serve(vec![
+            r#"{"token_type":"Bearer","access_token":"cccccccc","expires_in":3600}"#,
+        ]);
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":0,"refresh_token":"bbbbbbbb"}"#
+            .parse()
+            .unwrap();
+        let token = Bearer::<Refresh>::from_response(&json).unwrap();
+
+        let token = run(client.ensure_token(&HttpClient::new(), token)).unwrap();
+        assert_eq!("cccccccc", token.access_token());
+        assert_eq!("bbbbbbbb", token.lifetime().refresh_token());
+        assert_eq!(
+            vec!["grant_type=refresh_token&refresh_token=bbbbbbbb"],
+            server.join().unwrap()
+        );
+    }
+
+    #[test]
+    fn poll_device_token() {
+        let (client, server) = serve(vec![
+            r#"{"error":"authorization_pending"}"#,
+            TOKEN,
+        ]);
+        let json = json!({
+            "device_code": "dddddddd",
+            "user_code": "WDJB-MJHT",
+            "verification_uri": "https://example.com/device",
+            "expires_in": 1800,
+            "interval": 0,
+        });
+        let device = DeviceAuthorization::from_response(&json).unwrap();
+
+        let token = run(client.poll_device_token(&HttpClient::new(), &device)).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!(2, server.join().unwrap().len());
+    }
+
+    #[test]
+    fn poll_device_token_expired() {
+        let (client, _) = serve(vec![]);
+        let json = json!({
+            "device_code": "dddddddd",
+            "user_code": "WDJB-MJHT",
+            "verification_uri": "https://example.com/device",
+            "expires_in": 0,
+        });
+        let device = DeviceAuthorization::from_response(&json).unwrap();
+
+        match run(client.poll_device_token(&HttpClient::new(), &device)) {
+            Err(ClientError::OAuth2(ref err)) => {
+                assert_eq!(OAuth2ErrorCode::ExpiredToken, err.code);
+            },
+            result => panic!("{:?}", result),
+        }
+    }
+}
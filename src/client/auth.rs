@@ -0,0 +1,260 @@
+//! Client authentication.
+
+use base64;
+use chrono::{Duration, Utc};
+use serde_json::{Map, Value};
+use url::form_urlencoded::Serializer;
+use url::Url;
+
+use client::ClientError;
+use client::mtls::ClientCertificate;
+use jwt::{SigningKey, random_jti};
+use secret::Secret;
+
+/// `client_assertion_type` of JWT client assertions.
+///
+/// See [RFC 7523, section 2.2](https://tools.ietf.org/html/rfc7523#section-2.2).
+pub const JWT_BEARER_ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// Methods of authenticating the client to the provider.
+///
+/// See [OpenID Connect Core 1.0, section
+/// 9](https://openid.net/specs/openid-connect-core-1_0.html#ClientAuthentication).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientAuth {
+    /// Client ID and secret in the `Authorization` header using HTTP Basic authentication.
+    ///
+    /// See [RFC 6749, section 2.3.1](http://tools.ietf.org/html/rfc6749#section-2.3.1).
+    ClientSecretBasic,
+
+    /// Client ID and secret in the request body.
+    ///
+    /// Although not recommended by the RFC, some providers require it.
+    ///
+    /// See [RFC 6749, section 2.3.1](http://tools.ietf.org/html/rfc6749#section-2.3.1).
+    ClientSecretPost,
+
+    /// JWT assertion signed with HS256 using the client secret as the key.
+    ///
+    /// See [RFC 7523, section 2.2](https://tools.ietf.org/html/rfc7523#section-2.2).
+    ClientSecretJwt,
+
+    /// JWT assertion signed with the client's private key.
+    ///
+    /// See [RFC 7523, section 2.2](https://tools.ietf.org/html/rfc7523#section-2.2).
+    PrivateKeyJwt(SigningKey),
+
+    /// Client certificate presented during the TLS handshake. The client ID is sent in the
+    /// request body, and issued tokens are bound to the certificate.
+    ///
+    /// The HTTP client must be configured to present the same certificate.
+    ///
+    /// See [RFC 8705, section 2.1](https://tools.ietf.org/html/rfc8705#section-2.1).
+    TlsClientAuth(ClientCertificate),
+
+    /// No authentication, for public clients. The client ID is sent in the request body, unless
+    /// it is empty.
+    None,
+}
+
+impl ClientAuth {
+    /// Returns the `token_endpoint_auth_method` name of the method.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ClientAuth::ClientSecretBasic => "client_secret_basic",
+            ClientAuth::ClientSecretPost => "client_secret_post",
+            ClientAuth::ClientSecretJwt => "client_secret_jwt",
+            ClientAuth::PrivateKeyJwt(_) => "private_key_jwt",
+            ClientAuth::TlsClientAuth(_) => "tls_client_auth",
+            ClientAuth::None => "none",
+        }
+    }
+
+    /// Returns true if the method sends `client_id` in the request body.
+    pub(crate) fn client_id_in_body(&self) -> bool {
+        *self != ClientAuth::ClientSecretBasic
+    }
+
+    /// Adds credentials to a request to an endpoint, returning the `Authorization` header value,
+    /// if any.
+    pub(crate) fn authenticate(
+        &self,
+        client_id: &str,
+        client_secret: &Secret,
+        audience: &Url,
+        body: &mut Serializer<String>,
+    ) -> Result<Option<String>, ClientError> {
+        match *self {
+            ClientAuth::ClientSecretBasic => {
+                let credentials = format!("{}:{}", client_id, client_secret.secret());
+                return Ok(Some(format!("Basic {}", base64::encode(&credentials))));
+            },
+            ClientAuth::ClientSecretPost => {
+                body.append_pair("client_id", client_id);
+                body.append_pair("client_secret", client_secret.secret());
+            },
+            ClientAuth::ClientSecretJwt => {
+                let key = SigningKey::hs256(client_secret.secret());
+                append_assertion(body, client_id, audience, &key)?;
+            },
+            ClientAuth::PrivateKeyJwt(ref key) => {
+                append_assertion(body, client_id, audience, key)?;
+            },
+            ClientAuth::TlsClientAuth(_) => {
+                body.append_pair("client_id", client_id);
+            },
+            ClientAuth::None => {
+                if !client_id.is_empty() {
+                    body.append_pair("client_id", client_id);
+                }
+            },
+        }
+        Ok(None)
+    }
+
+    /// Records the certificate a token response is bound to in its `cnf` member.
+    pub(crate) fn confirm(&self, mut json: Value) -> Value {
+        if let ClientAuth::TlsClientAuth(ref cert) = *self {
+            if let Some(cnf) = json.as_object_mut()
+                .map(|obj| obj.entry("cnf").or_insert_with(|| Value::Object(Map::new())))
+                .and_then(Value::as_object_mut)
+            {
+                cnf.entry("x5t#S256").or_insert_with(|| Value::from(cert.thumbprint()));
+            }
+        }
+        json
+    }
+}
+
+fn append_assertion(
+    body: &mut Serializer<String>,
+    client_id: &str,
+    audience: &Url,
+    key: &SigningKey,
+) -> Result<(), ClientError> {
+    let assertion = client_assertion(client_id, audience, key)?;
+    body.append_pair("client_id", client_id);
+    body.append_pair("client_assertion_type", JWT_BEARER_ASSERTION_TYPE);
+    body.append_pair("client_assertion", &assertion);
+    Ok(())
+}
+
+/// Signs a client assertion valid for five minutes, with a random `jti`.
+///
+/// See [RFC 7523, section 3](https://tools.ietf.org/html/rfc7523#section-3).
+fn client_assertion(
+    client_id: &str,
+    audience: &Url,
+    key: &SigningKey,
+) -> Result<String, ClientError> {
+    let now = Utc::now();
+
+    let mut claims = Map::new();
+    claims.insert(String::from("iss"), Value::from(client_id));
+    claims.insert(String::from("sub"), Value::from(client_id));
+    claims.insert(String::from("aud"), Value::from(audience.as_str()));
+    claims.insert(String::from("jti"), Value::from(random_jti()?));
+    claims.insert(String::from("iat"), Value::from(now.timestamp()));
+    claims.insert(String::from("exp"), Value::from((now + Duration::minutes(5)).timestamp()));
+
+    Ok(key.sign(&Value::Object(claims))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use url::Url;
+    use url::form_urlencoded::{self, Serializer};
+
+    use client::mtls::tests::client_certificate;
+    use jwt::tests::{ec_key, jwk};
+    use jwt::{Jwks, Jwt, SigningKey};
+    use secret::Secret;
+    use super::{ClientAuth, JWT_BEARER_ASSERTION_TYPE};
+
+    fn authenticate(auth: &ClientAuth) -> (Option<String>, HashMap<String, String>) {
+        let mut body = Serializer::new(String::new());
+        let audience = Url::parse("https://example.com/token").unwrap();
+        let header = auth.authenticate("foo", &Secret::from("bar"), &audience, &mut body)
+            .unwrap();
+        let body = form_urlencoded::parse(body.finish().as_bytes()).into_owned().collect();
+        (header, body)
+    }
+
+    #[test]
+    fn client_secret_basic() {
+        let (header, body) = authenticate(&ClientAuth::ClientSecretBasic);
+        assert_eq!(Some(String::from("Basic Zm9vOmJhcg==")), header);
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn client_secret_post() {
+        let (header, body) = authenticate(&ClientAuth::ClientSecretPost);
+        assert_eq!(None, header);
+        assert_eq!("foo", body["client_id"]);
+        assert_eq!("bar", body["client_secret"]);
+    }
+
+    #[test]
+    fn client_secret_jwt() {
+        let (header, body) = authenticate(&ClientAuth::ClientSecretJwt);
+        assert_eq!(None, header);
+        assert_eq!(JWT_BEARER_ASSERTION_TYPE, body["client_assertion_type"]);
+        assert!(!body.contains_key("client_secret"));
+
+        let jwt = Jwt::decode(&body["client_assertion"]).unwrap();
+        assert_eq!("HS256", jwt.header().alg);
+        assert_eq!(Some(&json!("foo")), jwt.claims().get("sub"));
+    }
+
+    #[test]
+    fn private_key_jwt() {
+        let key = ec_key();
+        let auth = ClientAuth::PrivateKeyJwt(
+            SigningKey::from_private_key(key.clone()).unwrap().kid(String::from("1"))
+        );
+        let (header, body) = authenticate(&auth);
+        assert_eq!(None, header);
+        assert_eq!("foo", body["client_id"]);
+
+        let jwt = Jwt::decode(&body["client_assertion"]).unwrap();
+        jwt.verify(&Jwks { keys: vec![jwk(&key, "1")] }).unwrap();
+        let claims = jwt.claims();
+        assert_eq!(Some(&json!("foo")), claims.get("iss"));
+        assert_eq!(Some(&json!("https://example.com/token")), claims.get("aud"));
+        assert!(claims.get("jti").is_some());
+        assert!(claims["exp"].as_i64() > claims["iat"].as_i64());
+    }
+
+    #[test]
+    fn none() {
+        let (header, body) = authenticate(&ClientAuth::None);
+        assert_eq!(None, header);
+        assert_eq!("foo", body["client_id"]);
+        assert_eq!(1, body.len());
+
+        let mut body = Serializer::new(String::new());
+        let audience = Url::parse("https://example.com/token").unwrap();
+        ClientAuth::None.authenticate("", &Secret::from(""), &audience, &mut body).unwrap();
+        assert_eq!("", body.finish());
+    }
+
+    #[test]
+    fn tls_client_auth() {
+        let cert = client_certificate();
+        let auth = ClientAuth::TlsClientAuth(cert.clone());
+        let (header, body) = authenticate(&auth);
+        assert_eq!(None, header);
+        assert_eq!("foo", body["client_id"]);
+        assert_eq!(1, body.len());
+
+        let json = auth.confirm(json!({"access_token": "aaaaaaaa"}));
+        assert_eq!(json!({"x5t#S256": cert.thumbprint()}), json["cnf"]);
+        let json = auth.confirm(json!({"cnf": {"x5t#S256": "bbbb"}}));
+        assert_eq!(json!({"x5t#S256": "bbbb"}), json["cnf"]);
+        let json = ClientAuth::None.confirm(json!({"access_token": "aaaaaaaa"}));
+        assert_eq!(None, json.get("cnf"));
+    }
+}
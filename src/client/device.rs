@@ -0,0 +1,153 @@
+//! Device authorization.
+
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Utc, Duration};
+use serde_json::Value;
+
+use client::response::{FromResponse, ParseError};
+
+/// Default polling interval in seconds.
+const DEFAULT_INTERVAL: u64 = 5;
+
+/// Device authorization response.
+///
+/// See [RFC 8628, section 3.2](https://tools.ietf.org/html/rfc8628#section-3.2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires: DateTime<Utc>,
+    interval: u64,
+}
+
+impl DeviceAuthorization {
+    /// Returns the device verification code.
+    pub fn device_code(&self) -> &str { &self.device_code }
+
+    /// Returns the code the user should enter at the verification URI.
+    pub fn user_code(&self) -> &str { &self.user_code }
+
+    /// Returns the verification URI the user should visit.
+    pub fn verification_uri(&self) -> &str { &self.verification_uri }
+
+    /// Returns the verification URI including the user code, if available.
+    pub fn verification_uri_complete(&self) -> Option<&str> {
+        self.verification_uri_complete.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the expiry time of the device code.
+    pub fn expires(&self) -> &DateTime<Utc> { &self.expires }
+
+    /// Returns the minimum amount of time to wait between polling requests.
+    pub fn interval(&self) -> StdDuration { StdDuration::from_secs(self.interval) }
+}
+
+impl FromResponse for DeviceAuthorization {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let device_code = obj.get("device_code")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("device_code", "string"))?;
+        let user_code = obj.get("user_code")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("user_code", "string"))?;
+
+        // Google uses the pre-standard name verification_url.
+        let verification_uri = obj.get("verification_uri")
+            .or_else(|| obj.get("verification_url"))
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("verification_uri", "string"))?;
+        let verification_uri_complete = obj.get("verification_uri_complete")
+            .and_then(Value::as_str);
+
+        let expires_in = obj.get("expires_in")
+            .and_then(Value::as_i64)
+            .ok_or(ParseError::ExpectedFieldType("expires_in", "i64"))?;
+
+        let interval = match obj.get("interval") {
+            Some(interval) => {
+                interval.as_u64().ok_or(ParseError::ExpectedFieldType("interval", "u64"))?
+            },
+            None => DEFAULT_INTERVAL,
+        };
+
+        Ok(DeviceAuthorization {
+            device_code: device_code.into(),
+            user_code: user_code.into(),
+            verification_uri: verification_uri.into(),
+            verification_uri_complete: verification_uri_complete.map(Into::into),
+            expires: Utc::now() + Duration::seconds(expires_in),
+            interval,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration as StdDuration;
+
+    use chrono::{Utc, Duration};
+
+    use client::response::{FromResponse, ParseError};
+    use super::DeviceAuthorization;
+
+    #[test]
+    fn from_response() {
+        let json = r#"
+            {
+                "device_code":"GmRhmhcxhwAzkoEqiMEg_DnyEysNkuNhszIySk9eS",
+                "user_code":"WDJB-MJHT",
+                "verification_uri":"https://example.com/device",
+                "verification_uri_complete":"https://example.com/device?user_code=WDJB-MJHT",
+                "expires_in":1800,
+                "interval":10
+            }
+        "#.parse().unwrap();
+        let device = DeviceAuthorization::from_response(&json).unwrap();
+        assert_eq!("GmRhmhcxhwAzkoEqiMEg_DnyEysNkuNhszIySk9eS", device.device_code());
+        assert_eq!("WDJB-MJHT", device.user_code());
+        assert_eq!("https://example.com/device", device.verification_uri());
+        assert_eq!(
+            Some("https://example.com/device?user_code=WDJB-MJHT"),
+            device.verification_uri_complete()
+        );
+        assert!(device.expires() > &Utc::now());
+        assert!(device.expires() <= &(Utc::now() + Duration::seconds(1800)));
+        assert_eq!(StdDuration::from_secs(10), device.interval());
+    }
+
+    #[test]
+    fn from_response_default_interval() {
+        let json = r#"
+            {
+                "device_code":"aaaaaaaa",
+                "user_code":"bbbbbbbb",
+                "verification_url":"https://www.google.com/device",
+                "expires_in":1800
+            }
+        "#.parse().unwrap();
+        let device = DeviceAuthorization::from_response(&json).unwrap();
+        assert_eq!("https://www.google.com/device", device.verification_uri());
+        assert_eq!(None, device.verification_uri_complete());
+        assert_eq!(StdDuration::from_secs(5), device.interval());
+    }
+
+    #[test]
+    fn from_response_without_user_code() {
+        let json = r#"
+            {
+                "device_code":"aaaaaaaa",
+                "verification_uri":"https://example.com/device",
+                "expires_in":1800
+            }
+        "#.parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("user_code", "string"),
+            DeviceAuthorization::from_response(&json).unwrap_err()
+        );
+    }
+}
@@ -1,12 +1,16 @@
 use std::error::Error;
+use std::sync::Arc;
 use std::{fmt, io};
 
+#[cfg(feature = "reqwest-client")]
 use reqwest;
+use openssl::error::ErrorStack;
 use serde_json;
 use url;
 
 use client::response::ParseError;
 use error::OAuth2Error;
+use jwt::JwtError;
 
 /// Errors that can occur during authorization.
 #[derive(Debug)]
@@ -18,8 +22,15 @@ pub enum ClientError {
     Url(url::ParseError),
 
     /// Reqwest error.
+    #[cfg(feature = "reqwest-client")]
     Reqwest(reqwest::Error),
 
+    /// HTTP transport error.
+    Transport(Box<dyn Error + Send + Sync>),
+
+    /// Unexpected HTTP response status.
+    Status(u16),
+
     /// JSON error.
     Json(serde_json::Error),
 
@@ -28,6 +39,25 @@ pub enum ClientError {
 
     /// OAuth 2.0 error.
     OAuth2(OAuth2Error),
+
+    /// JWT error.
+    Jwt(JwtError),
+
+    /// Cryptographic error.
+    Crypto(ErrorStack),
+
+    /// Provider does not support the endpoint.
+    UnsupportedEndpoint(&'static str),
+
+    /// Redirect state does not match the authorization request.
+    InvalidState,
+
+    /// Provider requires PKCE, but no code challenge or verifier was given.
+    PkceRequired,
+
+    /// Error shared between callers, such as a failed token refresh returned to every caller
+    /// that waited for it.
+    Shared(Arc<ClientError>),
 }
 
 impl fmt::Display for ClientError {
@@ -35,10 +65,20 @@ impl fmt::Display for ClientError {
         match *self {
             ClientError::Io(ref err) => write!(f, "{}", err),
             ClientError::Url(ref err) => write!(f, "{}", err),
+            #[cfg(feature = "reqwest-client")]
             ClientError::Reqwest(ref err) => write!(f, "{}", err),
+            ClientError::Transport(ref err) => write!(f, "{}", err),
+            ClientError::Status(status) => write!(f, "Unexpected HTTP status {}", status),
             ClientError::Json(ref err) => write!(f, "{}", err),
             ClientError::Parse(ref err) => write!(f, "{}", err),
             ClientError::OAuth2(ref err) => write!(f, "{}", err),
+            ClientError::Jwt(ref err) => write!(f, "{}", err),
+            ClientError::Crypto(ref err) => write!(f, "{}", err),
+            ClientError::UnsupportedEndpoint(endpoint) =>
+                write!(f, "Provider does not support the {} endpoint", endpoint),
+            ClientError::InvalidState => write!(f, "Invalid redirect state"),
+            ClientError::PkceRequired => write!(f, "Provider requires PKCE"),
+            ClientError::Shared(ref err) => write!(f, "{}", err),
         }
     }
 }
@@ -46,23 +86,41 @@ impl fmt::Display for ClientError {
 impl Error for ClientError {
     fn description(&self) -> &str {
         match *self {
-            ClientError::Io(ref err) => err.description(),
-            ClientError::Url(ref err) => err.description(),
-            ClientError::Reqwest(ref err) => err.description(),
-            ClientError::Json(ref err) => err.description(),
-            ClientError::Parse(ref err) => err.description(),
-            ClientError::OAuth2(ref err) => err.description(),
+            ClientError::Io(_) => "IO error",
+            ClientError::Url(_) => "URL error",
+            #[cfg(feature = "reqwest-client")]
+            ClientError::Reqwest(_) => "Reqwest error",
+            ClientError::Transport(_) => "HTTP transport error",
+            ClientError::Status(_) => "Unexpected HTTP status",
+            ClientError::Json(_) => "JSON error",
+            ClientError::Parse(_) => "Response parse error",
+            ClientError::OAuth2(_) => "OAuth 2.0 API error",
+            ClientError::Jwt(_) => "JWT error",
+            ClientError::Crypto(_) => "Cryptographic error",
+            ClientError::UnsupportedEndpoint(_) => "Provider does not support the endpoint",
+            ClientError::InvalidState => "Invalid redirect state",
+            ClientError::PkceRequired => "Provider requires PKCE",
+            ClientError::Shared(_) => "Shared error",
         }
     }
 
-    fn cause(&self) -> Option<&Error> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match *self {
             ClientError::Io(ref err) => Some(err),
             ClientError::Url(ref err) => Some(err),
+            #[cfg(feature = "reqwest-client")]
             ClientError::Reqwest(ref err) => Some(err),
+            ClientError::Transport(ref err) => Some(&**err),
             ClientError::Json(ref err) => Some(err),
             ClientError::Parse(ref err) => Some(err),
             ClientError::OAuth2(ref err) => Some(err),
+            ClientError::Jwt(ref err) => Some(err),
+            ClientError::Crypto(ref err) => Some(err),
+            ClientError::Shared(ref err) => Some(&**err),
+            ClientError::Status(_)
+            | ClientError::UnsupportedEndpoint(_)
+            | ClientError::InvalidState
+            | ClientError::PkceRequired => None,
         }
     }
 }
@@ -79,7 +137,10 @@ macro_rules! impl_from {
 
 impl_from!(ClientError::Io, io::Error);
 impl_from!(ClientError::Url, url::ParseError);
+#[cfg(feature = "reqwest-client")]
 impl_from!(ClientError::Reqwest, reqwest::Error);
 impl_from!(ClientError::Json, serde_json::Error);
 impl_from!(ClientError::Parse, ParseError);
 impl_from!(ClientError::OAuth2, OAuth2Error);
+impl_from!(ClientError::Jwt, JwtError);
+impl_from!(ClientError::Crypto, ErrorStack);
@@ -0,0 +1,374 @@
+//! Token exchange.
+//!
+//! See [RFC 8693](https://tools.ietf.org/html/rfc8693).
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use url::form_urlencoded::Serializer;
+
+use client::response::{FromResponse, ParseError, optional_object, optional_string};
+use secret::Secret;
+use token::Confirmation;
+
+/// `grant_type` of token exchange requests.
+///
+/// See [RFC 8693, section 2.1](https://tools.ietf.org/html/rfc8693#section-2.1).
+pub const TOKEN_EXCHANGE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+
+/// Token type identifiers.
+///
+/// See [RFC 8693, section 3](https://tools.ietf.org/html/rfc8693#section-3).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum TokenType {
+    /// An OAuth 2.0 access token.
+    AccessToken,
+
+    /// An OAuth 2.0 refresh token.
+    RefreshToken,
+
+    /// An OpenID Connect ID token.
+    IdToken,
+
+    /// A base64url-encoded SAML 1.1 assertion.
+    Saml1,
+
+    /// A base64url-encoded SAML 2.0 assertion.
+    Saml2,
+
+    /// A JWT.
+    Jwt,
+
+    /// Another token type identifier.
+    Other(String),
+}
+
+impl TokenType {
+    /// Returns the token type identifier.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            TokenType::AccessToken => "urn:ietf:params:oauth:token-type:access_token",
+            TokenType::RefreshToken => "urn:ietf:params:oauth:token-type:refresh_token",
+            TokenType::IdToken => "urn:ietf:params:oauth:token-type:id_token",
+            TokenType::Saml1 => "urn:ietf:params:oauth:token-type:saml1",
+            TokenType::Saml2 => "urn:ietf:params:oauth:token-type:saml2",
+            TokenType::Jwt => "urn:ietf:params:oauth:token-type:jwt",
+            TokenType::Other(ref s) => s,
+        }
+    }
+}
+
+impl From<&str> for TokenType {
+    fn from(s: &str) -> TokenType {
+        match s {
+            "urn:ietf:params:oauth:token-type:access_token" => TokenType::AccessToken,
+            "urn:ietf:params:oauth:token-type:refresh_token" => TokenType::RefreshToken,
+            "urn:ietf:params:oauth:token-type:id_token" => TokenType::IdToken,
+            "urn:ietf:params:oauth:token-type:saml1" => TokenType::Saml1,
+            "urn:ietf:params:oauth:token-type:saml2" => TokenType::Saml2,
+            "urn:ietf:params:oauth:token-type:jwt" => TokenType::Jwt,
+            s => TokenType::Other(s.to_owned()),
+        }
+    }
+}
+
+impl From<String> for TokenType {
+    fn from(s: String) -> TokenType {
+        TokenType::from(&s[..])
+    }
+}
+
+impl From<TokenType> for String {
+    fn from(token_type: TokenType) -> String {
+        token_type.as_str().to_owned()
+    }
+}
+
+/// Token exchange request.
+///
+/// See [RFC 8693, section 2.1](https://tools.ietf.org/html/rfc8693#section-2.1).
+///
+/// # Examples
+///
+/// ```
+/// use inth_oauth2::client::exchange::{TokenExchange, TokenType};
+///
+/// let exchange = TokenExchange::new("INCOMING_TOKEN", TokenType::AccessToken)
+///     .actor("SERVICE_TOKEN", TokenType::Jwt)
+///     .audience("https://backend.example.com")
+///     .scope("read")
+///     .requested_token_type(TokenType::AccessToken);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenExchange {
+    subject_token: Secret,
+    subject_token_type: TokenType,
+    actor_token: Option<(Secret, TokenType)>,
+    resources: Vec<String>,
+    audiences: Vec<String>,
+    scope: Option<String>,
+    requested_token_type: Option<TokenType>,
+}
+
+impl TokenExchange {
+    /// Creates a request exchanging a subject token, representing the party on whose behalf the
+    /// request is made.
+    pub fn new(subject_token: &str, subject_token_type: TokenType) -> Self {
+        TokenExchange {
+            subject_token: Secret::from(subject_token),
+            subject_token_type,
+            actor_token: None,
+            resources: Vec::new(),
+            audiences: Vec::new(),
+            scope: None,
+            requested_token_type: None,
+        }
+    }
+
+    /// Sets the actor token, representing the party acting on behalf of the subject.
+    pub fn actor(mut self, actor_token: &str, actor_token_type: TokenType) -> Self {
+        self.actor_token = Some((Secret::from(actor_token), actor_token_type));
+        self
+    }
+
+    /// Adds a URI of a resource where the issued token is to be used.
+    pub fn resource(mut self, resource: &str) -> Self {
+        self.resources.push(resource.into());
+        self
+    }
+
+    /// Adds a logical name of a service where the issued token is to be used.
+    pub fn audience(mut self, audience: &str) -> Self {
+        self.audiences.push(audience.into());
+        self
+    }
+
+    /// Sets the requested scope.
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Sets the requested type of the issued token.
+    pub fn requested_token_type(mut self, token_type: TokenType) -> Self {
+        self.requested_token_type = Some(token_type);
+        self
+    }
+
+    pub(crate) fn body(&self) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("grant_type", TOKEN_EXCHANGE_GRANT_TYPE);
+        for resource in &self.resources {
+            body.append_pair("resource", resource);
+        }
+        for audience in &self.audiences {
+            body.append_pair("audience", audience);
+        }
+        if let Some(ref scope) = self.scope {
+            body.append_pair("scope", scope);
+        }
+        if let Some(ref token_type) = self.requested_token_type {
+            body.append_pair("requested_token_type", token_type.as_str());
+        }
+        body.append_pair("subject_token", self.subject_token.secret());
+        body.append_pair("subject_token_type", self.subject_token_type.as_str());
+        if let Some((ref token, ref token_type)) = self.actor_token {
+            body.append_pair("actor_token", token.secret());
+            body.append_pair("actor_token_type", token_type.as_str());
+        }
+        body
+    }
+}
+
+/// Token issued by a token exchange.
+///
+/// The issued token is not necessarily an access token, in which case the token type is `N_A`.
+///
+/// See [RFC 8693, section 2.2.1](https://tools.ietf.org/html/rfc8693#section-2.2.1).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangedToken {
+    access_token: Secret,
+    issued_token_type: TokenType,
+    token_type: String,
+    expires: Option<DateTime<Utc>>,
+    scope: Option<String>,
+    refresh_token: Option<Secret>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cnf: Option<Confirmation>,
+}
+
+impl ExchangedToken {
+    /// Returns the issued token, in the `access_token` field whatever its type.
+    pub fn access_token(&self) -> &str { self.access_token.secret() }
+
+    /// Returns the type of the issued token.
+    pub fn issued_token_type(&self) -> &TokenType { &self.issued_token_type }
+
+    /// Returns how the issued token is used to access resources, such as `Bearer`, or `N_A`.
+    pub fn token_type(&self) -> &str { &self.token_type }
+
+    /// Returns the expiry time of the issued token, if provided.
+    pub fn expires(&self) -> Option<&DateTime<Utc>> { self.expires.as_ref() }
+
+    /// Returns the scope of the issued token, if it differs from the requested scope.
+    pub fn scope(&self) -> Option<&str> { self.scope.as_ref().map(|s| &s[..]) }
+
+    /// Returns the refresh token, if issued.
+    pub fn refresh_token(&self) -> Option<&str> {
+        self.refresh_token.as_ref().map(Secret::secret)
+    }
+
+    /// Returns the confirmation of the key the issued token is bound to, if any.
+    ///
+    /// See [RFC 8693, section 2.2.1](https://tools.ietf.org/html/rfc8693#section-2.2.1).
+    pub fn confirmation(&self) -> Option<&Confirmation> { self.cnf.as_ref() }
+
+    /// Returns true if the issued token has expired.
+    pub fn expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires < Utc::now())
+    }
+}
+
+impl FromResponse for ExchangedToken {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let access_token = obj.get("access_token")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("access_token", "string"))?;
+        let issued_token_type = obj.get("issued_token_type")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("issued_token_type", "string"))?;
+        let token_type = obj.get("token_type")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("token_type", "string"))?;
+        let expires = match obj.get("expires_in") {
+            None => None,
+            Some(expires_in) => {
+                let expires_in = expires_in.as_i64()
+                    .ok_or(ParseError::ExpectedFieldType("expires_in", "i64"))?;
+                Some(Utc::now() + Duration::seconds(expires_in))
+            },
+        };
+
+        Ok(ExchangedToken {
+            access_token: access_token.into(),
+            issued_token_type: issued_token_type.into(),
+            token_type: token_type.into(),
+            expires,
+            scope: optional_string(obj, "scope")?,
+            refresh_token: optional_string(obj, "refresh_token")?.map(Secret::from),
+            cnf: optional_object(obj, "cnf")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use serde_json;
+
+    use client::response::{FromResponse, ParseError};
+    use token::Confirmation;
+    use super::{ExchangedToken, TokenExchange, TokenType};
+
+    #[test]
+    fn token_type() {
+        assert_eq!(TokenType::Jwt, TokenType::from("urn:ietf:params:oauth:token-type:jwt"));
+        assert_eq!("urn:example", TokenType::from("urn:example").as_str());
+        assert_eq!(
+            r#""urn:ietf:params:oauth:token-type:id_token""#,
+            serde_json::to_string(&TokenType::IdToken).unwrap()
+        );
+    }
+
+    #[test]
+    fn body() {
+        let exchange = TokenExchange::new("aaaa", TokenType::AccessToken)
+            .actor("bbbb", TokenType::Jwt)
+            .resource("https://a.example.com")
+            .resource("https://b.example.com")
+            .audience("backend")
+            .scope("read")
+            .requested_token_type(TokenType::RefreshToken);
+        assert_eq!(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Atoken-exchange\
+             &resource=https%3A%2F%2Fa.example.com\
+             &resource=https%3A%2F%2Fb.example.com\
+             &audience=backend\
+             &scope=read\
+             &requested_token_type=urn%3Aietf%3Aparams%3Aoauth%3Atoken-type%3Arefresh_token\
+             &subject_token=aaaa\
+             &subject_token_type=urn%3Aietf%3Aparams%3Aoauth%3Atoken-type%3Aaccess_token\
+             &actor_token=bbbb\
+             &actor_token_type=urn%3Aietf%3Aparams%3Aoauth%3Atoken-type%3Ajwt",
+            exchange.body().finish()
+        );
+        assert!(!format!("{:?}", exchange).contains("aaaa"));
+    }
+
+    #[test]
+    fn from_response() {
+        let json = r#"
+            {
+                "access_token":"cccc",
+                "issued_token_type":"urn:ietf:params:oauth:token-type:access_token",
+                "token_type":"Bearer",
+                "expires_in":60,
+                "scope":"read"
+            }
+        "#.parse().unwrap();
+        let token = ExchangedToken::from_response(&json).unwrap();
+        assert_eq!("cccc", token.access_token());
+        assert_eq!(&TokenType::AccessToken, token.issued_token_type());
+        assert_eq!("Bearer", token.token_type());
+        assert_eq!(Some("read"), token.scope());
+        assert_eq!(None, token.refresh_token());
+        assert_eq!(None, token.confirmation());
+        assert!(*token.expires().unwrap() <= Utc::now() + Duration::seconds(60));
+        assert!(!token.expired());
+    }
+
+    #[test]
+    fn from_response_not_access_token() {
+        let json = r#"
+            {
+                "access_token":"dddd",
+                "issued_token_type":"urn:ietf:params:oauth:token-type:saml2",
+                "token_type":"N_A"
+            }
+        "#.parse().unwrap();
+        let token = ExchangedToken::from_response(&json).unwrap();
+        assert_eq!(&TokenType::Saml2, token.issued_token_type());
+        assert_eq!("N_A", token.token_type());
+        assert_eq!(None, token.expires());
+    }
+
+    #[test]
+    fn from_response_with_cnf() {
+        let json = r#"
+            {
+                "access_token":"cccc",
+                "issued_token_type":"urn:ietf:params:oauth:token-type:access_token",
+                "token_type":"DPoP",
+                "cnf":{"jkt":"bbbb"}
+            }
+        "#.parse().unwrap();
+        let token = ExchangedToken::from_response(&json).unwrap();
+        assert_eq!(
+            Some(&Confirmation { x5t_s256: None, jkt: Some(String::from("bbbb")) }),
+            token.confirmation()
+        );
+        let json = serde_json::to_value(&token).unwrap();
+        assert_eq!(Some(&json!({"jkt": "bbbb"})), json.get("cnf"));
+    }
+
+    #[test]
+    fn from_response_without_issued_token_type() {
+        let json = r#"{"access_token":"cccc","token_type":"Bearer"}"#.parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("issued_token_type", "string"),
+            ExchangedToken::from_response(&json).unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! HTTP transport.
+//!
+//! `Client` sends requests through any type implementing `HttpClient`. With the default
+//! `reqwest-client` feature, it is implemented for `reqwest::Client`. `MemoryClient` returns
+//! canned responses, for testing without sockets.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+
+use url::Url;
+
+use client::ClientError;
+
+/// HTTP request method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// GET.
+    Get,
+
+    /// POST.
+    Post,
+}
+
+impl Method {
+    /// Returns the method name.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        }
+    }
+}
+
+/// HTTP request to a provider.
+///
+/// `Debug` omits the body and the value of the `Authorization` header, which contain
+/// credentials.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// Request method.
+    pub method: Method,
+
+    /// Request URL.
+    pub url: Url,
+
+    /// Header names and values.
+    pub headers: Vec<(String, String)>,
+
+    /// Request body, such as a URL-encoded form.
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Creates a GET request accepting a JSON response.
+    pub fn get(url: Url) -> Self {
+        HttpRequest {
+            method: Method::Get,
+            url,
+            headers: vec![(String::from("Accept"), String::from("application/json"))],
+            body: Vec::new(),
+        }
+    }
+
+    /// Returns the first value of a header, ignoring case in its name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+impl fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let headers: Vec<_> = self.headers.iter()
+            .map(|(name, value)| {
+                if name.eq_ignore_ascii_case("authorization") {
+                    (&name[..], "[redacted]")
+                } else {
+                    (&name[..], &value[..])
+                }
+            })
+            .collect();
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.url.as_str())
+            .field("headers", &headers)
+            .field("body", &format_args!("[{} bytes]", self.body.len()))
+            .finish()
+    }
+}
+
+/// HTTP response from a provider.
+///
+/// `Debug` omits the body, which may contain tokens.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// Status code.
+    pub status: u16,
+
+    /// Header names and values.
+    pub headers: Vec<(String, String)>,
+
+    /// Response body.
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Returns true if the status code is 2xx.
+    pub fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    /// Returns the first value of a header, ignoring case in its name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+impl fmt::Debug for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("HttpResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("body", &format_args!("[{} bytes]", self.body.len()))
+            .finish()
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| &value[..])
+}
+
+/// HTTP transport.
+pub trait HttpClient {
+    /// Sends a request and reads the entire response.
+    ///
+    /// Non-2xx responses are returned as `Ok`; OAuth 2.0 error responses are parsed by the
+    /// caller. Transport failures should be returned as `ClientError::Transport`.
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, ClientError>;
+}
+
+impl<H: HttpClient + ?Sized> HttpClient for &H {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, ClientError> {
+        (**self).execute(request)
+    }
+}
+
+#[cfg(feature = "reqwest-client")]
+mod reqwest_client {
+    use std::io::Read;
+
+    use reqwest;
+
+    use client::ClientError;
+    use super::{HttpClient, HttpRequest, HttpResponse, Method};
+
+    impl HttpClient for reqwest::Client {
+        fn execute(&self, request: HttpRequest) -> Result<HttpResponse, ClientError> {
+            let method = match request.method {
+                Method::Get => reqwest::Method::GET,
+                Method::Post => reqwest::Method::POST,
+            };
+
+            let mut builder = self.request(method, request.url);
+            for (name, value) in request.headers {
+                builder = builder.header(&name[..], &value[..]);
+            }
+            let mut response = builder.body(request.body).send()?;
+
+            let headers = response.headers().iter()
+                .filter_map(|(name, value)| {
+                    value.to_str().ok().map(|value| (name.as_str().into(), value.into()))
+                })
+                .collect();
+            let mut body = Vec::new();
+            response.read_to_end(&mut body)?;
+
+            Ok(HttpResponse {
+                status: response.status().as_u16(),
+                headers,
+                body,
+            })
+        }
+    }
+}
+
+/// In-memory HTTP transport returning queued responses.
+///
+/// Records every request it receives.
+///
+/// # Examples
+///
+/// ```
+/// use inth_oauth2::Client;
+/// use inth_oauth2::client::http::MemoryClient;
+/// use inth_oauth2::provider::GitHub;
+///
+/// let http = MemoryClient::new();
+/// http.push_json(200, r#"{"token_type":"bearer","access_token":"aaaaaaaa","scope":""}"#);
+///
+/// let client = Client::new(GitHub, String::from("id"), String::from("secret"), None);
+/// client.request_token(&http, "code").unwrap();
+/// assert_eq!("https://github.com/login/oauth/access_token", http.requests()[0].url.as_str());
+/// ```
+#[derive(Default)]
+pub struct MemoryClient {
+    responses: Mutex<VecDeque<HttpResponse>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MemoryClient {
+    /// Creates a transport with no queued responses.
+    pub fn new() -> Self {
+        MemoryClient::default()
+    }
+
+    /// Queues a response.
+    pub fn push(&self, response: HttpResponse) {
+        self.responses.lock().unwrap().push_back(response);
+    }
+
+    /// Queues a JSON response with a status code.
+    pub fn push_json(&self, status: u16, json: &str) {
+        self.push(HttpResponse {
+            status,
+            headers: vec![(String::from("Content-Type"), String::from("application/json"))],
+            body: json.as_bytes().to_vec(),
+        });
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl fmt::Debug for MemoryClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("MemoryClient")
+            .field("responses", &self.responses.lock().unwrap().len())
+            .field("requests", &self.requests.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl HttpClient for MemoryClient {
+    /// Returns the next queued response, or a `Transport` error if there are none.
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, ClientError> {
+        self.requests.lock().unwrap().push(request);
+        self.responses.lock().unwrap()
+            .pop_front()
+            .ok_or_else(|| ClientError::Transport("no queued response".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use client::ClientError;
+    use super::{HttpClient, HttpRequest, HttpResponse, MemoryClient, Method};
+
+    #[test]
+    fn header_ignores_case() {
+        let request = HttpRequest::get(Url::parse("https://example.com/").unwrap());
+        assert_eq!(Method::Get, request.method);
+        assert_eq!(Some("application/json"), request.header("accept"));
+        assert_eq!(None, request.header("content-type"));
+    }
+
+    #[test]
+    fn memory_client() {
+        let http = MemoryClient::new();
+        http.push_json(404, "{}");
+
+        let uri = Url::parse("https://example.com/").unwrap();
+        let response = http.execute(HttpRequest::get(uri.clone())).unwrap();
+        assert_eq!(404, response.status);
+        assert!(!response.is_success());
+        assert_eq!(b"{}".to_vec(), response.body);
+
+        match http.execute(HttpRequest::get(uri)) {
+            Err(ClientError::Transport(_)) => {},
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(2, http.requests().len());
+    }
+
+    #[test]
+    fn request_debug_redacted() {
+        let mut request = HttpRequest::get(Url::parse("https://example.com/").unwrap());
+        request.headers.push((String::from("Authorization"), String::from("Basic Zm9vOmJhcg==")));
+        request.body = b"client_secret=bar".to_vec();
+
+        let debug = format!("{:?}", request);
+        assert!(!debug.contains("Zm9vOmJhcg=="));
+        assert!(!debug.contains("bar"));
+    }
+
+    #[test]
+    fn response_debug_redacted() {
+        let response = HttpResponse {
+            status: 200,
+            headers: vec![(String::from("Content-Type"), String::from("application/json"))],
+            body: br#"{"access_token":"aaaaaaaa"}"#.to_vec(),
+        };
+        let debug = format!("{:?}", response);
+        assert!(debug.contains("application/json"));
+        assert!(!debug.contains("aaaaaaaa"));
+
+        let http = MemoryClient::new();
+        http.push(response);
+        let debug = format!("{:?}", http);
+        assert!(debug.contains("responses: 1"));
+        assert!(!debug.contains("aaaaaaaa"));
+    }
+}
@@ -0,0 +1,208 @@
+//! Token introspection.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+use client::response::{
+    FromResponse,
+    ParseError,
+    optional_array,
+    optional_object,
+    optional_string,
+    optional_timestamp,
+    string_or_array,
+};
+use token::{AuthorizationDetail, Confirmation};
+
+/// Token introspection response.
+///
+/// See [RFC 7662, section 2.2](https://tools.ietf.org/html/rfc7662#section-2.2).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Introspection {
+    /// Whether the token is currently active.
+    pub active: bool,
+
+    /// Scopes associated with the token.
+    pub scope: Option<String>,
+
+    /// Client identifier of the client that requested the token.
+    pub client_id: Option<String>,
+
+    /// Human-readable identifier of the resource owner who authorized the token.
+    pub username: Option<String>,
+
+    /// Type of the token.
+    pub token_type: Option<String>,
+
+    /// Expiry time of the token.
+    pub exp: Option<DateTime<Utc>>,
+
+    /// Time the token was issued.
+    pub iat: Option<DateTime<Utc>>,
+
+    /// Time before which the token is not to be used.
+    pub nbf: Option<DateTime<Utc>>,
+
+    /// Subject of the token, usually the resource owner.
+    pub sub: Option<String>,
+
+    /// Intended audiences of the token.
+    pub aud: Vec<String>,
+
+    /// Issuer of the token.
+    pub iss: Option<String>,
+
+    /// Identifier of the token.
+    pub jti: Option<String>,
+
+    /// Confirmation of the key the token is bound to.
+    ///
+    /// See [RFC 8705, section 3.2](https://tools.ietf.org/html/rfc8705#section-3.2).
+    pub cnf: Option<Confirmation>,
+
+    /// Authorization details granted for the token.
+    ///
+    /// See [RFC 9396, section 9.2](https://tools.ietf.org/html/rfc9396#section-9.2).
+    pub authorization_details: Vec<AuthorizationDetail>,
+
+    /// Additional fields not defined in RFC 7662.
+    pub extra: Map<String, Value>,
+}
+
+const FIELDS: &[&str] = &[
+    "active", "scope", "client_id", "username", "token_type", "exp", "iat", "nbf", "sub", "aud",
+    "iss", "jti", "cnf", "authorization_details",
+];
+
+impl FromResponse for Introspection {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let active = obj.get("active")
+            .and_then(Value::as_bool)
+            .ok_or(ParseError::ExpectedFieldType("active", "bool"))?;
+
+        let extra = obj.iter()
+            .filter(|&(key, _)| !FIELDS.contains(&&key[..]))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        Ok(Introspection {
+            active,
+            scope: optional_string(obj, "scope")?,
+            client_id: optional_string(obj, "client_id")?,
+            username: optional_string(obj, "username")?,
+            token_type: optional_string(obj, "token_type")?,
+            exp: optional_timestamp(obj, "exp")?,
+            iat: optional_timestamp(obj, "iat")?,
+            nbf: optional_timestamp(obj, "nbf")?,
+            sub: optional_string(obj, "sub")?,
+            aud: string_or_array(obj, "aud")?,
+            iss: optional_string(obj, "iss")?,
+            jti: optional_string(obj, "jti")?,
+            cnf: optional_object(obj, "cnf")?,
+            authorization_details: optional_array(obj, "authorization_details")?,
+            extra,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::Value;
+
+    use client::response::{FromResponse, ParseError};
+    use super::Introspection;
+
+    #[test]
+    fn from_response_inactive() {
+        let json = r#"{"active":false}"#.parse().unwrap();
+        let introspection = Introspection::from_response(&json).unwrap();
+        assert!(!introspection.active);
+        assert_eq!(None, introspection.scope);
+        assert!(introspection.aud.is_empty());
+        assert!(introspection.extra.is_empty());
+    }
+
+    #[test]
+    fn from_response_active() {
+        let json = r#"
+            {
+                "active":true,
+                "client_id":"l238j323ds-23ij4",
+                "username":"jdoe",
+                "scope":"read write dolphin",
+                "sub":"Z5O3upPC88QrAjx00dis",
+                "aud":"https://protected.example.net/resource",
+                "iss":"https://server.example.com/",
+                "exp":1419356238,
+                "iat":1419350238,
+                "extension_field":"twenty-seven"
+            }
+        "#.parse().unwrap();
+        let introspection = Introspection::from_response(&json).unwrap();
+        assert!(introspection.active);
+        assert_eq!(Some(String::from("l238j323ds-23ij4")), introspection.client_id);
+        assert_eq!(Some(String::from("jdoe")), introspection.username);
+        assert_eq!(Some(String::from("read write dolphin")), introspection.scope);
+        assert_eq!(Some(String::from("Z5O3upPC88QrAjx00dis")), introspection.sub);
+        assert_eq!(vec![String::from("https://protected.example.net/resource")], introspection.aud);
+        assert_eq!(Some(String::from("https://server.example.com/")), introspection.iss);
+        assert_eq!(Some(Utc.timestamp_opt(1419356238, 0).unwrap()), introspection.exp);
+        assert_eq!(Some(Utc.timestamp_opt(1419350238, 0).unwrap()), introspection.iat);
+        assert_eq!(None, introspection.jti);
+        assert_eq!(
+            Some(&Value::String(String::from("twenty-seven"))),
+            introspection.extra.get("extension_field")
+        );
+        assert_eq!(1, introspection.extra.len());
+    }
+
+    #[test]
+    fn from_response_aud_array() {
+        let json = r#"{"active":true,"aud":["a","b"]}"#.parse().unwrap();
+        let introspection = Introspection::from_response(&json).unwrap();
+        assert_eq!(vec![String::from("a"), String::from("b")], introspection.aud);
+    }
+
+    #[test]
+    fn from_response_cnf() {
+        let json = r#"{"active":true,"cnf":{"x5t#S256":"bbbb"}}"#.parse().unwrap();
+        let introspection = Introspection::from_response(&json).unwrap();
+        assert_eq!(Some(String::from("bbbb")), introspection.cnf.unwrap().x5t_s256);
+        assert!(introspection.extra.is_empty());
+    }
+
+    #[test]
+    fn from_response_authorization_details() {
+        let json = r#"
+            {
+                "active":true,
+                "authorization_details":[{"type":"payment_initiation","actions":["initiate"]}]
+            }
+        "#.parse().unwrap();
+        let introspection = Introspection::from_response(&json).unwrap();
+        assert_eq!(1, introspection.authorization_details.len());
+        assert_eq!("payment_initiation", introspection.authorization_details[0].detail_type);
+        assert!(introspection.extra.is_empty());
+    }
+
+    #[test]
+    fn from_response_without_active() {
+        let json = r#"{"scope":"foo"}"#.parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("active", "bool"),
+            Introspection::from_response(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn from_response_invalid_exp() {
+        let json = r#"{"active":true,"exp":"soon"}"#.parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("exp", "i64"),
+            Introspection::from_response(&json).unwrap_err()
+        );
+    }
+}
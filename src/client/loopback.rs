@@ -0,0 +1,357 @@
+//! Loopback redirect listener.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use url::Url;
+
+use client::{Client, ClientError};
+use provider::Provider;
+
+const SUCCESS_PAGE: &str = "<!DOCTYPE html>\n<title>Authorized</title>\n\
+    <p>Authorization complete. You may close this window.</p>\n";
+
+const FAILURE_PAGE: &str = "<!DOCTYPE html>\n<title>Not authorized</title>\n\
+    <p>Authorization failed. You may close this window.</p>\n";
+
+/// Maximum length of a request line and headers.
+const MAX_REQUEST_LEN: u64 = 8192;
+
+/// Interval between checks for connections.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Listens on the loopback interface for the authorization redirect of an installed application.
+///
+/// See [RFC 8252, section 7.3](https://tools.ietf.org/html/rfc8252#section-7.3).
+///
+/// # Examples
+///
+/// ```no_run
+/// use inth_oauth2::Client;
+/// use inth_oauth2::client::loopback::LoopbackListener;
+/// use inth_oauth2::client::random_state;
+/// use inth_oauth2::pkce::{ChallengeMethod, CodeVerifier};
+/// use inth_oauth2::provider::google::Installed;
+///
+/// let listener = LoopbackListener::bind().unwrap();
+/// let client = Client::new(
+///     Installed,
+///     String::from("CLIENT_ID"),
+///     String::from("CLIENT_SECRET"),
+///     Some(listener.redirect_uri().to_owned()),
+/// );
+///
+/// let state = random_state().unwrap();
+/// let verifier = CodeVerifier::new();
+/// let challenge = verifier.challenge(ChallengeMethod::S256);
+/// println!("{}", client.auth_uri_with_challenge(Some("scope"), Some(&state), &challenge));
+/// let code = listener.accept(&client, &state).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct LoopbackListener {
+    listener: TcpListener,
+    redirect_uri: String,
+    success_page: String,
+    failure_page: String,
+    timeout: Duration,
+}
+
+impl LoopbackListener {
+    /// Binds a listener to an ephemeral port on 127.0.0.1.
+    pub fn bind() -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::new(127, 0, 0, 1), 0))?;
+        let redirect_uri = format!("http://127.0.0.1:{}/", listener.local_addr()?.port());
+        Ok(LoopbackListener {
+            listener,
+            redirect_uri,
+            success_page: SUCCESS_PAGE.into(),
+            failure_page: FAILURE_PAGE.into(),
+            timeout: Duration::from_secs(300),
+        })
+    }
+
+    /// Returns the redirect URI to configure the client with.
+    pub fn redirect_uri(&self) -> &str { &self.redirect_uri }
+
+    /// Sets the HTML page served after a successful authorization.
+    pub fn success_page(mut self, html: String) -> Self {
+        self.success_page = html;
+        self
+    }
+
+    /// Sets the HTML page served after a failed authorization.
+    pub fn failure_page(mut self, html: String) -> Self {
+        self.failure_page = html;
+        self
+    }
+
+    /// Sets how long to wait for the authorization redirect. Defaults to five minutes.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Waits for the authorization redirect and returns the authorization code.
+    ///
+    /// Requests for other paths, such as `/favicon.ico`, are answered with 404 Not Found, and
+    /// malformed requests or redirects without the expected state, which may be forged by other
+    /// local processes or web pages, with 400 Bad Request. The redirect is parsed with
+    /// `Client::parse_redirect`.
+    ///
+    /// Returns an IO error of kind `TimedOut` if no redirect arrives before the timeout.
+    pub fn accept<P: Provider>(
+        &self,
+        client: &Client<P>,
+        state: &str,
+    ) -> Result<String, ClientError> {
+        let base = Url::parse(&self.redirect_uri)?;
+        let deadline = Instant::now() + self.timeout;
+        self.listener.set_nonblocking(true)?;
+        loop {
+            let mut stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(ClientError::from(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "timed out waiting for authorization redirect",
+                        )));
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                },
+                Err(err) => return Err(ClientError::from(err)),
+            };
+
+            let target = match read_request_target(&stream, deadline) {
+                Ok(target) => target,
+                Err(_) => {
+                    let _ = respond(&mut stream, "400 Bad Request", "");
+                    continue;
+                },
+            };
+
+            let uri = match base.join(&target) {
+                Ok(uri) => uri,
+                Err(_) => {
+                    let _ = respond(&mut stream, "400 Bad Request", "");
+                    continue;
+                },
+            };
+            if uri.path() != base.path() {
+                let _ = respond(&mut stream, "404 Not Found", "");
+                continue;
+            }
+
+            match client.parse_redirect(&uri, state) {
+                Err(ClientError::InvalidState) => {
+                    let _ = respond(&mut stream, "400 Bad Request", &self.failure_page);
+                },
+                result => {
+                    let page = if result.is_ok() { &self.success_page } else { &self.failure_page };
+                    respond(&mut stream, "200 OK", page)?;
+                    return result;
+                },
+            }
+        }
+    }
+}
+
+/// Reads an HTTP request, returning the request target.
+fn read_request_target(stream: &TcpStream, deadline: Instant) -> io::Result<String> {
+    let now = Instant::now();
+    let timeout = if deadline > now { deadline - now } else { Duration::from_millis(1) };
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(timeout.min(Duration::from_secs(10))))?;
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_LEN));
+
+    let mut request_line = String::new();
+    read_line(&mut reader, &mut request_line)?;
+
+    // Discard headers.
+    let mut line = String::new();
+    while read_line(&mut reader, &mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) if target.starts_with('/') => Ok(target.into()),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid request")),
+    }
+}
+
+/// Reads a line, returning an error if the request is cut off by its length limit.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<usize> {
+    let len = reader.read_line(line)?;
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "request too long"));
+    }
+    Ok(len)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: text/html; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {}",
+        status,
+        body.len(),
+        body,
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+
+    use url::Url;
+
+    use client::{Client, ClientError};
+    use error::OAuth2ErrorCode;
+    use provider::GitHub;
+    use super::LoopbackListener;
+
+    fn get(redirect_uri: &str, target: &str) -> String {
+        let uri = Url::parse(redirect_uri).unwrap();
+        let mut stream = TcpStream::connect(("127.0.0.1", uri.port().unwrap())).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n", target).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn client(listener: &LoopbackListener) -> Client<GitHub> {
+        Client::new(
+            GitHub,
+            String::from("foo"),
+            String::from("bar"),
+            Some(listener.redirect_uri().to_owned()),
+        )
+    }
+
+    #[test]
+    fn redirect_uri() {
+        let listener = LoopbackListener::bind().unwrap();
+        let uri = Url::parse(listener.redirect_uri()).unwrap();
+        assert_eq!("127.0.0.1", uri.host_str().unwrap());
+        assert_ne!(Some(0), uri.port());
+        assert_eq!("/", uri.path());
+    }
+
+    #[test]
+    fn accept() {
+        let listener = LoopbackListener::bind().unwrap()
+            .success_page(String::from("yay"));
+        let redirect_uri = listener.redirect_uri().to_owned();
+        let request = thread::spawn(move || {
+            let favicon = get(&redirect_uri, "/favicon.ico");
+            let callback = get(&redirect_uri, "/?code=baz&state=qux");
+            (favicon, callback)
+        });
+
+        let code = listener.accept(&client(&listener), "qux").unwrap();
+        assert_eq!("baz", code);
+
+        let (favicon, callback) = request.join().unwrap();
+        assert!(favicon.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(callback.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(callback.ends_with("\r\n\r\nyay"));
+    }
+
+    #[test]
+    fn accept_ignores_forged_redirect() {
+        let listener = LoopbackListener::bind().unwrap();
+        let redirect_uri = listener.redirect_uri().to_owned();
+        let request = thread::spawn(move || {
+            let missing = get(&redirect_uri, "/?code=evil");
+            let mismatched = get(&redirect_uri, "/?code=evil&state=quux");
+            let callback = get(&redirect_uri, "/?code=baz&state=qux");
+            (missing, mismatched, callback)
+        });
+
+        let code = listener.accept(&client(&listener), "qux").unwrap();
+        assert_eq!("baz", code);
+
+        let (missing, mismatched, callback) = request.join().unwrap();
+        assert!(missing.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(mismatched.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(callback.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn accept_ignores_invalid_target() {
+        let listener = LoopbackListener::bind().unwrap();
+        let redirect_uri = listener.redirect_uri().to_owned();
+        let request = thread::spawn(move || {
+            let ipv6 = get(&redirect_uri, "//[");
+            let empty_host = get(&redirect_uri, "/\\");
+            let callback = get(&redirect_uri, "/?code=baz&state=qux");
+            (ipv6, empty_host, callback)
+        });
+
+        let code = listener.accept(&client(&listener), "qux").unwrap();
+        assert_eq!("baz", code);
+
+        let (ipv6, empty_host, callback) = request.join().unwrap();
+        assert!(ipv6.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(empty_host.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(callback.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn accept_request_too_long() {
+        let listener = LoopbackListener::bind().unwrap();
+        let redirect_uri = listener.redirect_uri().to_owned();
+        let request = thread::spawn(move || {
+            // The connection may be reset before the response is read, since the listener stops
+            // reading the request.
+            let uri = Url::parse(&redirect_uri).unwrap();
+            let mut stream = TcpStream::connect(("127.0.0.1", uri.port().unwrap())).unwrap();
+            let _ = write!(stream, "GET /?code={} HTTP/1.1\r\n\r\n", "a".repeat(10000));
+            let _ = stream.read_to_string(&mut String::new());
+            get(&redirect_uri, "/?code=baz&state=qux")
+        });
+
+        assert_eq!("baz", listener.accept(&client(&listener), "qux").unwrap());
+        assert!(request.join().unwrap().starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[test]
+    fn accept_timeout() {
+        let listener = LoopbackListener::bind().unwrap()
+            .timeout(Duration::from_millis(100));
+        match listener.accept(&client(&listener), "qux") {
+            Err(ClientError::Io(ref err)) => assert_eq!(io::ErrorKind::TimedOut, err.kind()),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn accept_error() {
+        let listener = LoopbackListener::bind().unwrap()
+            .failure_page(String::from("nay"));
+        let redirect_uri = listener.redirect_uri().to_owned();
+        let request = thread::spawn(move || {
+            get(&redirect_uri, "/?error=access_denied&state=qux")
+        });
+
+        match listener.accept(&client(&listener), "qux") {
+            Err(ClientError::OAuth2(ref err)) => {
+                assert_eq!(OAuth2ErrorCode::AccessDenied, err.code);
+            },
+            result => panic!("{:?}", result),
+        }
+        assert!(request.join().unwrap().ends_with("\r\n\r\nnay"));
+    }
+}
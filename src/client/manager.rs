@@ -0,0 +1,283 @@
+//! Shared token management.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use chrono::{Duration, Utc};
+
+use client::http::HttpClient;
+use client::{Client, ClientError};
+use provider::Provider;
+use token::{Refresh, Token};
+
+/// Holds a refreshable token shared between threads, refreshing it before it expires.
+///
+/// At most one refresh is in flight at a time. Callers needing a token while it is being
+/// refreshed wait for the refresh to complete, so a rotated refresh token is never used twice. If
+/// the refresh fails, every caller that waited for it receives the same error.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate inth_oauth2;
+/// # extern crate reqwest;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use inth_oauth2::Client;
+/// use inth_oauth2::client::manager::TokenManager;
+/// use inth_oauth2::provider::google::Installed;
+///
+/// # fn main() {
+/// let client = Client::new(Installed, String::new(), String::new(), None);
+/// # let http = reqwest::Client::new();
+/// # let verifier = inth_oauth2::pkce::CodeVerifier::new();
+/// # let token = client.request_token_with_verifier(&http, "", &verifier).unwrap();
+/// let manager = Arc::new(TokenManager::new(client, token));
+///
+/// for _ in 0..4 {
+///     let manager = manager.clone();
+///     thread::spawn(move || {
+///         let http = reqwest::Client::new();
+///         let access_token = manager.access_token(&http).unwrap();
+///     });
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TokenManager<P: Provider> {
+    client: Client<P>,
+    margin: Duration,
+    state: Mutex<State<P::Token>>,
+    refreshed: Condvar,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    token: T,
+    refreshing: bool,
+    /// Incremented each time a refresh completes.
+    generation: u64,
+    /// Error of the last completed refresh, if it failed.
+    error: Option<Arc<ClientError>>,
+}
+
+impl<P> TokenManager<P>
+where P: Provider, P::Token: Token<Refresh> + Clone {
+    /// Creates a manager for a token issued to the client.
+    pub fn new(client: Client<P>, token: P::Token) -> Self {
+        TokenManager {
+            client,
+            margin: Duration::seconds(60),
+            state: Mutex::new(State { token, refreshing: false, generation: 0, error: None }),
+            refreshed: Condvar::new(),
+        }
+    }
+
+    /// Sets how long before expiry the token is refreshed. Defaults to 60 seconds.
+    pub fn margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Returns the client.
+    pub fn client(&self) -> &Client<P> { &self.client }
+
+    /// Returns a copy of the current token, without refreshing it.
+    ///
+    /// Useful for persisting the token after it has been refreshed.
+    pub fn token(&self) -> P::Token {
+        self.lock().token.clone()
+    }
+
+    /// Returns a valid access token, refreshing the token if it is within the margin of expiry.
+    ///
+    /// If another thread is already refreshing the token, waits for it to finish instead. Errors
+    /// refreshing the token are returned as `ClientError::Shared`, to the refreshing thread and
+    /// to every thread that waited for it.
+    pub fn access_token<H: HttpClient>(&self, http_client: &H) -> Result<String, ClientError> {
+        let mut state = self.lock();
+        loop {
+            if !self.needs_refresh(&state.token) {
+                return Ok(state.token.access_token().into());
+            }
+            if !state.refreshing {
+                break;
+            }
+
+            let generation = state.generation;
+            while state.generation == generation {
+                state = self.refreshed.wait(state).unwrap_or_else(|err| err.into_inner());
+            }
+            if let Some(ref err) = state.error {
+                return Err(ClientError::Shared(err.clone()));
+            }
+        }
+
+        state.refreshing = true;
+        state.error = None;
+        let token = state.token.clone();
+        drop(state);
+
+        let _guard = RefreshGuard(self);
+        match self.client.refresh_token(http_client, token, None) {
+            Ok(token) => {
+                let access_token = token.access_token().into();
+                self.lock().token = token;
+                Ok(access_token)
+            },
+            Err(err) => {
+                let err = Arc::new(err);
+                self.lock().error = Some(err.clone());
+                Err(ClientError::Shared(err))
+            },
+        }
+    }
+
+    fn needs_refresh(&self, token: &P::Token) -> bool {
+        *token.lifetime().expires() - self.margin <= Utc::now()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<P::Token>> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Clears the refreshing flag, completes the refresh generation and wakes waiting callers once the
+/// outcome of the refresh is stored, or if the refresh panics.
+struct RefreshGuard<'a, P: 'a + Provider>(&'a TokenManager<P>);
+
+impl<'a, P: 'a + Provider> Drop for RefreshGuard<'a, P> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.refreshing = false;
+        state.generation = state.generation.wrapping_add(1);
+        self.0.refreshed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration as StdDuration;
+
+    use chrono::Duration;
+
+    use client::http::{HttpClient, HttpRequest, HttpResponse, MemoryClient};
+    use client::response::FromResponse;
+    use client::{Client, ClientError};
+    use error::OAuth2ErrorCode;
+    use provider::google::Installed;
+    use token::{OidcBearer, Refresh, Token};
+    use super::TokenManager;
+
+    fn token(expires_in: i64) -> OidcBearer<Refresh> {
+        let json = json!({
+            "token_type": "Bearer",
+            "access_token": "aaaaaaaa",
+            "expires_in": expires_in,
+            "refresh_token": "bbbbbbbb",
+        });
+        OidcBearer::from_response(&json).unwrap()
+    }
+
+    fn manager(expires_in: i64) -> TokenManager<Installed> {
+        let client = Client::new(Installed, String::from("foo"), String::from("bar"), None);
+        TokenManager::new(client, token(expires_in))
+    }
+
+    const REFRESHED: &str =
+        r#"{"token_type":"Bearer","access_token":"cccccccc","expires_in":3600,"refresh_token":"dddddddd"}"#;
+
+    #[test]
+    fn access_token_valid() {
+        let http = MemoryClient::new();
+        assert_eq!("aaaaaaaa", manager(3600).access_token(&http).unwrap());
+        assert!(http.requests().is_empty());
+    }
+
+    #[test]
+    fn access_token_within_margin() {
+        let http = MemoryClient::new();
+        http.push_json(200, REFRESHED);
+
+        let manager = manager(30);
+        assert_eq!("cccccccc", manager.access_token(&http).unwrap());
+        assert_eq!("dddddddd", manager.token().lifetime().refresh_token());
+
+        let manager = manager.margin(Duration::seconds(0));
+        assert_eq!("cccccccc", manager.access_token(&http).unwrap());
+        assert_eq!(1, http.requests().len());
+    }
+
+    #[test]
+    fn access_token_single_refresh() {
+        let http = Arc::new(MemoryClient::new());
+        http.push_json(200, REFRESHED);
+        let manager = Arc::new(manager(0));
+
+        let threads: Vec<_> = (0..8).map(|_| {
+            let http = http.clone();
+            let manager = manager.clone();
+            thread::spawn(move || manager.access_token(&*http).unwrap())
+        }).collect();
+
+        for thread in threads {
+            assert_eq!("cccccccc", thread.join().unwrap());
+        }
+        assert_eq!(1, http.requests().len());
+    }
+
+    #[test]
+    fn access_token_error() {
+        let http = MemoryClient::new();
+        http.push_json(400, r#"{"error":"invalid_grant"}"#);
+        http.push_json(200, REFRESHED);
+
+        let manager = manager(0);
+        match manager.access_token(&http) {
+            Err(ClientError::Shared(ref err)) => match **err {
+                ClientError::OAuth2(_) => {},
+                ref err => panic!("{:?}", err),
+            },
+            result => panic!("{:?}", result),
+        }
+        assert_eq!("aaaaaaaa", manager.token().access_token());
+        assert_eq!("cccccccc", manager.access_token(&http).unwrap());
+    }
+
+    /// Delays responses, so that concurrent callers wait for the refresh in flight.
+    struct SlowClient(MemoryClient);
+    impl HttpClient for SlowClient {
+        fn execute(&self, request: HttpRequest) -> Result<HttpResponse, ClientError> {
+            thread::sleep(StdDuration::from_millis(200));
+            self.0.execute(request)
+        }
+    }
+
+    #[test]
+    fn access_token_shared_error() {
+        let http = Arc::new(SlowClient(MemoryClient::new()));
+        http.0.push_json(400, r#"{"error":"invalid_grant"}"#);
+        let manager = Arc::new(manager(0));
+
+        let threads: Vec<_> = (0..8).map(|_| {
+            let http = http.clone();
+            let manager = manager.clone();
+            thread::spawn(move || manager.access_token(&*http).unwrap_err())
+        }).collect();
+
+        for thread in threads {
+            match thread.join().unwrap() {
+                ClientError::Shared(ref err) => match **err {
+                    ClientError::OAuth2(ref err) => {
+                        assert_eq!(OAuth2ErrorCode::InvalidGrant, err.code);
+                    },
+                    ref err => panic!("{:?}", err),
+                },
+                err => panic!("{:?}", err),
+            }
+        }
+        assert_eq!(1, http.0.requests().len());
+    }
+}
@@ -1,20 +1,55 @@
 //! Client.
 
+#[cfg(feature = "reqwest-client")]
+mod async_client;
 mod error;
 
+pub mod assertion;
+pub mod auth;
+pub mod device;
+pub mod exchange;
+pub mod http;
+pub mod introspection;
+pub mod loopback;
+pub mod manager;
+pub mod mtls;
+pub mod par;
 pub mod response;
+#[cfg(feature = "reqwest-client")]
+pub use self::async_client::AsyncClient;
+pub use self::auth::ClientAuth;
 pub use self::error::ClientError;
 
-use reqwest;
-use reqwest::header::{ACCEPT, CONTENT_TYPE};
-use serde_json::{self, Value};
-use url::form_urlencoded::Serializer;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{self, Utc};
+use openssl::memcmp;
+use serde_json::{self, Map, Value};
+use url::form_urlencoded::{self, Serializer};
 use url::Url;
 
-use client::response::FromResponse;
-use error::OAuth2Error;
+use client::assertion::JWT_BEARER_GRANT_TYPE;
+use client::device::DeviceAuthorization;
+use client::exchange::{ExchangedToken, TokenExchange};
+use client::http::{HttpClient, HttpRequest, HttpResponse, Method};
+use client::introspection::Introspection;
+use client::par::PushedAuthorization;
+use client::response::{FromResponse, ParseError};
+use dpop::DpopKey;
+use error::{OAuth2Error, OAuth2ErrorCode};
+use jwt::{SigningKey, random_jti};
+use pkce::{CodeChallenge, CodeVerifier};
 use provider::Provider;
-use token::{Lifetime, Refresh, Token};
+use scope::Scope;
+use secret::Secret;
+use store::{StoreKey, StoredRefreshError, TokenStore};
+use token::{AuthorizationDetail, Lifetime, Refresh, Token, TokenTypeHint};
+
+/// `typ` header of request objects.
+///
+/// See [RFC 9101, section 10.8](https://tools.ietf.org/html/rfc9101#section-10.8).
+const REQUEST_OBJECT_TYPE: &str = "oauth-authz-req+jwt";
 
 /// OAuth 2.0 client.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -26,10 +61,16 @@ pub struct Client<P> {
     pub client_id: String,
 
     /// Client secret.
-    pub client_secret: String,
+    pub client_secret: Secret,
 
     /// Redirect URI.
     pub redirect_uri: Option<String>,
+
+    /// Client authentication method, by default the provider's.
+    pub auth: ClientAuth,
+
+    /// DPoP key proving possession of tokens, if tokens are to be DPoP-bound.
+    pub dpop: Option<DpopKey>,
 }
 
 impl<P: Provider> Client<P> {
@@ -54,22 +95,146 @@ impl<P: Provider> Client<P> {
         client_secret: String,
         redirect_uri: Option<String>,
     ) -> Self {
+        let auth = provider.client_auth();
         Client {
             provider,
             client_id,
-            client_secret,
+            client_secret: Secret::from(client_secret),
             redirect_uri,
+            auth,
+            dpop: None,
+        }
+    }
+
+    /// Sets the client authentication method.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use inth_oauth2::{Client, ClientAuth};
+    /// use inth_oauth2::jwt::SigningKey;
+    /// use inth_oauth2::provider::google::Installed;
+    ///
+    /// let pem = std::fs::read("key.pem").unwrap();
+    /// let key = SigningKey::from_pem(&pem).unwrap().kid(String::from("KEY_ID"));
+    /// let client = Client::new(Installed, String::from("CLIENT_ID"), String::new(), None)
+    ///     .with_auth(ClientAuth::PrivateKeyJwt(key));
+    /// ```
+    pub fn with_auth(mut self, auth: ClientAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sets the DPoP key, sending proofs of possession with token requests so that issued tokens
+    /// are bound to it.
+    ///
+    /// The provider's token type must be `token::Dpop`, since issued tokens have the `DPoP` token
+    /// type rather than `Bearer`. See `dpop::DpopKey` for an example.
+    ///
+    /// See [RFC 9449, section 5](https://tools.ietf.org/html/rfc9449#section-5).
+    pub fn with_dpop(mut self, key: DpopKey) -> Self {
+        self.dpop = Some(key);
+        self
+    }
+
+    /// Returns the key identifying a user's token from this client in a `TokenStore`.
+    pub fn store_key(&self, user: &str) -> StoreKey {
+        StoreKey::new(self.provider.token_uri().as_str(), &self.client_id, user)
+    }
+
+    /// Formats a scope with the provider's delimiter, for passing as a `scope` parameter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inth_oauth2::Client;
+    /// use inth_oauth2::provider::GitHub;
+    /// use inth_oauth2::scope::Scope;
+    ///
+    /// let client = Client::new(
+    ///     GitHub,
+    ///     String::from("CLIENT_ID"),
+    ///     String::from("CLIENT_SECRET"),
+    ///     None,
+    /// );
+    ///
+    /// let scope = client.format_scope(&Scope::from("repo gist"));
+    /// assert_eq!("repo,gist", scope);
+    /// ```
+    pub fn format_scope(&self, scope: &Scope) -> String {
+        scope.join(self.provider.scope_delimiter())
+    }
+
+    /// Parses a scope with the provider's delimiter.
+    pub fn parse_scope(&self, scope: &str) -> Scope {
+        Scope::parse(scope, self.provider.scope_delimiter())
+    }
+
+    /// Returns the scope granted to a token requested with a scope.
+    ///
+    /// If the token response omitted the scope, it is identical to the requested scope.
+    ///
+    /// See [RFC 6749, section 5.1](http://tools.ietf.org/html/rfc6749#section-5.1).
+    pub fn granted_scope<L: Lifetime, T: Token<L>>(&self, token: &T, requested: &Scope) -> Scope {
+        match token.scope() {
+            Some(scope) => self.parse_scope(scope),
+            None => requested.clone(),
         }
     }
 
+    /// Returns the requested scope tokens not granted to a token, such as those the user
+    /// declined.
+    pub fn denied_scope<L: Lifetime, T: Token<L>>(&self, token: &T, requested: &Scope) -> Scope {
+        requested.difference(&self.granted_scope(token, requested))
+    }
+
     /// Returns an authorization endpoint URI to direct the user to.
     ///
+    /// Providers which require PKCE reject authorization requests without a code challenge; use
+    /// `auth_uri_with_challenge` instead.
+    ///
     /// See [RFC 6749, section 3.1](http://tools.ietf.org/html/rfc6749#section-3.1).
     ///
     /// # Examples
     ///
     /// ```
     /// use inth_oauth2::Client;
+    /// use inth_oauth2::provider::google::Web;
+    ///
+    /// let client = Client::new(
+    ///     Web,
+    ///     String::from("CLIENT_ID"),
+    ///     String::from("CLIENT_SECRET"),
+    ///     Some(String::from("https://example.com/callback")),
+    /// );
+    ///
+    /// let auth_uri = client.auth_uri(
+    ///     Some("https://www.googleapis.com/auth/userinfo.email"),
+    ///     None,
+    /// );
+    /// ```
+    pub fn auth_uri(&self, scope: Option<&str>, state: Option<&str>) -> Url {
+        self.auth_uri_with(scope, state, None, &[])
+    }
+
+    /// Returns an authorization endpoint URI requesting a scope, formatted with the provider's
+    /// delimiter.
+    ///
+    /// See [RFC 6749, section 3.3](http://tools.ietf.org/html/rfc6749#section-3.3).
+    pub fn auth_uri_with_scope(&self, scope: &Scope, state: Option<&str>) -> Url {
+        let scope = self.format_scope(scope);
+        self.auth_uri_with(Some(&scope), state, None, &[])
+    }
+
+    /// Returns an authorization endpoint URI with a PKCE code challenge.
+    ///
+    /// See [RFC 7636, section 4.3](https://tools.ietf.org/html/rfc7636#section-4.3).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inth_oauth2::Client;
+    /// use inth_oauth2::pkce::{ChallengeMethod, CodeVerifier};
     /// use inth_oauth2::provider::google::Installed;
     ///
     /// let client = Client::new(
@@ -79,189 +244,1767 @@ impl<P: Provider> Client<P> {
     ///     Some(String::from("urn:ietf:wg:oauth:2.0:oob")),
     /// );
     ///
-    /// let auth_uri = client.auth_uri(
+    /// let verifier = CodeVerifier::new();
+    /// let auth_uri = client.auth_uri_with_challenge(
     ///     Some("https://www.googleapis.com/auth/userinfo.email"),
     ///     None,
+    ///     &verifier.challenge(ChallengeMethod::S256),
     /// );
     /// ```
-    pub fn auth_uri(&self, scope: Option<&str>, state: Option<&str>) -> Url
-    {
-        let mut uri = self.provider.auth_uri().clone();
+    pub fn auth_uri_with_challenge(
+        &self,
+        scope: Option<&str>,
+        state: Option<&str>,
+        challenge: &CodeChallenge,
+    ) -> Url {
+        self.auth_uri_with(scope, state, Some(challenge), &[])
+    }
+
+    /// Returns an authorization endpoint URI requesting authorization details, in addition to or
+    /// instead of scope.
+    ///
+    /// See [RFC 9396, section 3](https://tools.ietf.org/html/rfc9396#section-3).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use inth_oauth2::Client;
+    /// use inth_oauth2::provider::google::Web;
+    /// use inth_oauth2::token::AuthorizationDetail;
+    ///
+    /// let client = Client::new(
+    ///     Web,
+    ///     String::from("CLIENT_ID"),
+    ///     String::from("CLIENT_SECRET"),
+    ///     Some(String::from("https://example.com/callback")),
+    /// );
+    ///
+    /// let detail = AuthorizationDetail {
+    ///     actions: vec![String::from("initiate")],
+    ///     ..AuthorizationDetail::new("payment_initiation")
+    /// };
+    /// let auth_uri = client.auth_uri_with_details(None, Some("STATE"), None, &[detail]);
+    /// ```
+    pub fn auth_uri_with_details(
+        &self,
+        scope: Option<&str>,
+        state: Option<&str>,
+        challenge: Option<&CodeChallenge>,
+        details: &[AuthorizationDetail],
+    ) -> Url {
+        self.auth_uri_with(scope, state, challenge, details)
+    }
 
+    fn auth_uri_with(
+        &self,
+        scope: Option<&str>,
+        state: Option<&str>,
+        challenge: Option<&CodeChallenge>,
+        details: &[AuthorizationDetail],
+    ) -> Url {
+        let mut uri = self.provider.auth_uri().clone();
         {
             let mut query = uri.query_pairs_mut();
-
-            query.append_pair("response_type", "code");
-            query.append_pair("client_id", &self.client_id);
-
-            if let Some(ref redirect_uri) = self.redirect_uri {
-                query.append_pair("redirect_uri", redirect_uri);
-            }
-            if let Some(scope) = scope {
-                query.append_pair("scope", scope);
-            }
-            if let Some(state) = state {
-                query.append_pair("state", state);
+            query.extend_pairs(self.auth_params(scope, state, challenge));
+            if !details.is_empty() {
+                query.append_pair("authorization_details", &authorization_details_param(details));
             }
         }
-
         uri
     }
 
-    fn post_token(
-        &self,
-        http_client: &reqwest::Client,
-        mut body: Serializer<String>,
-    ) -> Result<Value, ClientError> {
-        if self.provider.credentials_in_body() {
-            body.append_pair("client_id", &self.client_id);
-            body.append_pair("client_secret", &self.client_secret);
+    /// Returns the authorization request parameters, beginning with `response_type` and
+    /// `client_id`.
+    fn auth_params<'a>(
+        &'a self,
+        scope: Option<&'a str>,
+        state: Option<&'a str>,
+        challenge: Option<&'a CodeChallenge>,
+    ) -> Vec<(&'static str, &'a str)> {
+        let mut params = vec![("response_type", "code"), ("client_id", &self.client_id[..])];
+        if let Some(ref redirect_uri) = self.redirect_uri {
+            params.push(("redirect_uri", redirect_uri));
         }
+        if let Some(scope) = scope {
+            params.push(("scope", scope));
+        }
+        if let Some(state) = state {
+            params.push(("state", state));
+        }
+        if let Some(challenge) = challenge {
+            params.push(("code_challenge", challenge.challenge()));
+            params.push(("code_challenge_method", challenge.method().as_str()));
+        }
+        params
+    }
 
-        let body = body.finish();
-
-        let mut response = http_client
-            .post(self.provider.token_uri().clone())
-            .basic_auth(&self.client_id, Some(&self.client_secret))
-            .header(ACCEPT, "application/json")
-            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-            .body(body)
-            .send()?;
+    /// Returns an authorization endpoint URI whose parameters are carried in a request object
+    /// signed by the client, such as the key used for `ClientAuth::PrivateKeyJwt`.
+    ///
+    /// See [RFC 9101, section 5.1](https://tools.ietf.org/html/rfc9101#section-5.1).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use inth_oauth2::Client;
+    /// use inth_oauth2::jwt::SigningKey;
+    /// use inth_oauth2::provider::google::Web;
+    ///
+    /// let pem = std::fs::read("key.pem").unwrap();
+    /// let key = SigningKey::from_pem(&pem).unwrap();
+    /// let client = Client::new(
+    ///     Web,
+    ///     String::from("CLIENT_ID"),
+    ///     String::from("CLIENT_SECRET"),
+    ///     Some(String::from("https://example.com/callback")),
+    /// );
+    ///
+    /// let auth_uri = client.signed_auth_uri(&key, Some("openid"), Some("STATE"), None, &[])
+    ///     .unwrap();
+    /// ```
+    pub fn signed_auth_uri(
+        &self,
+        key: &SigningKey,
+        scope: Option<&str>,
+        state: Option<&str>,
+        challenge: Option<&CodeChallenge>,
+        details: &[AuthorizationDetail],
+    ) -> Result<Url, ClientError> {
+        let request = self.request_object(key, scope, state, challenge, details)?;
+        let mut uri = self.provider.auth_uri().clone();
+        uri.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("request", &request);
+        Ok(uri)
+    }
 
-        let json = serde_json::from_reader(&mut response)?;
+    /// Signs a request object containing the authorization request parameters, valid for five
+    /// minutes.
+    ///
+    /// The request object may be hosted by the client and passed by reference using
+    /// `request_uri_auth_uri`. Its audience is the provider's issuer, if known, otherwise the
+    /// authorization endpoint URI.
+    ///
+    /// See [RFC 9101, section 4](https://tools.ietf.org/html/rfc9101#section-4).
+    pub fn request_object(
+        &self,
+        key: &SigningKey,
+        scope: Option<&str>,
+        state: Option<&str>,
+        challenge: Option<&CodeChallenge>,
+        details: &[AuthorizationDetail],
+    ) -> Result<String, ClientError> {
+        self.check_pkce(challenge.is_some())?;
+        let now = Utc::now();
+        // URL parsing adds a trailing slash to issuers without a path.
+        let audience = match self.provider.issuer() {
+            Some(issuer) => issuer.as_str().trim_end_matches('/'),
+            None => self.provider.auth_uri().as_str(),
+        };
 
-        let error = OAuth2Error::from_response(&json);
+        let mut claims = Map::new();
+        for (name, value) in self.auth_params(scope, state, challenge) {
+            claims.insert(String::from(name), Value::from(value));
+        }
+        if !details.is_empty() {
+            claims.insert(String::from("authorization_details"), serde_json::to_value(details)?);
+        }
+        claims.insert(String::from("iss"), Value::from(&self.client_id[..]));
+        claims.insert(String::from("aud"), Value::from(audience));
+        claims.insert(String::from("jti"), Value::from(random_jti()?));
+        claims.insert(String::from("iat"), Value::from(now.timestamp()));
+        claims.insert(String::from("exp"), Value::from((now + chrono::Duration::minutes(5)).timestamp()));
 
-        if let Ok(error) = error {
-            Err(ClientError::from(error))
-        } else {
-            Ok(json)
+        let mut header = Map::new();
+        header.insert(String::from("typ"), Value::from(REQUEST_OBJECT_TYPE));
+        if let Some(kid) = key.key_id() {
+            header.insert(String::from("kid"), Value::from(kid));
         }
+        Ok(key.sign_with_header(header, &Value::Object(claims))?)
     }
 
-    /// Requests an access token using an authorization code.
+    /// Returns an authorization endpoint URI referencing a request object by URI.
     ///
-    /// See [RFC 6749, section 4.1.3](http://tools.ietf.org/html/rfc6749#section-4.1.3).
-    pub fn request_token(
+    /// See [RFC 9101, section 5.2](https://tools.ietf.org/html/rfc9101#section-5.2).
+    pub fn request_uri_auth_uri(&self, request_uri: &str) -> Url {
+        let mut uri = self.provider.auth_uri().clone();
+        uri.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("request_uri", request_uri);
+        uri
+    }
+
+    /// Pushes the authorization request parameters to the provider, returning a request URI to
+    /// pass to `pushed_auth_uri`.
+    ///
+    /// The parameters are those `auth_uri` and `auth_uri_with_challenge` would include, posted
+    /// with client authentication to the pushed authorization request endpoint.
+    ///
+    /// See [RFC 9126, section 2.1](https://tools.ietf.org/html/rfc9126#section-2.1).
+    pub fn push_authorization_request<H: HttpClient>(
         &self,
-        http_client: &reqwest::Client,
-        code: &str,
-    ) -> Result<P::Token, ClientError> {
-        let mut body = Serializer::new(String::new());
-        body.append_pair("grant_type", "authorization_code");
-        body.append_pair("code", code);
+        http_client: &H,
+        scope: Option<&str>,
+        state: Option<&str>,
+        challenge: Option<&CodeChallenge>,
+        details: &[AuthorizationDetail],
+    ) -> Result<PushedAuthorization, ClientError> {
+        self.check_pkce(challenge.is_some())?;
+        let uri = self.pushed_authorization_request_uri()?;
+        let body = self.pushed_authorization_body(scope, state, challenge, details);
+        let json = self.post_form(http_client, uri, body)?;
+        let par = PushedAuthorization::from_response(&json)?;
+        Ok(par)
+    }
 
-        if let Some(ref redirect_uri) = self.redirect_uri {
-            body.append_pair("redirect_uri", redirect_uri);
+    /// Returns `ClientError::PkceRequired` if the provider requires PKCE and it is not used.
+    pub(crate) fn check_pkce(&self, pkce: bool) -> Result<(), ClientError> {
+        if pkce || !self.provider.pkce_required() {
+            Ok(())
+        } else {
+            Err(ClientError::PkceRequired)
         }
+    }
 
-        let json = self.post_token(http_client, body)?;
-        let token = P::Token::from_response(&json)?;
-        Ok(token)
+    pub(crate) fn pushed_authorization_request_uri(&self) -> Result<&Url, ClientError> {
+        self.provider.pushed_authorization_request_uri()
+            .ok_or(ClientError::UnsupportedEndpoint("pushed authorization request"))
     }
-}
 
-impl<P> Client<P> where P: Provider, P::Token: Token<Refresh> {
-    /// Refreshes an access token.
-    ///
-    /// See [RFC 6749, section 6](http://tools.ietf.org/html/rfc6749#section-6).
-    pub fn refresh_token(
+    pub(crate) fn pushed_authorization_body(
         &self,
-        http_client: &reqwest::Client,
-        token: P::Token,
         scope: Option<&str>,
-    ) -> Result<P::Token, ClientError> {
+        state: Option<&str>,
+        challenge: Option<&CodeChallenge>,
+        details: &[AuthorizationDetail],
+    ) -> Serializer<String> {
+        let client_id_in_body = self.auth.client_id_in_body();
         let mut body = Serializer::new(String::new());
-        body.append_pair("grant_type", "refresh_token");
-        body.append_pair("refresh_token", token.lifetime().refresh_token());
+        body.extend_pairs(
+            self.auth_params(scope, state, challenge)
+                .into_iter()
+                .filter(|&(name, _)| name != "client_id" || !client_id_in_body),
+        );
+        if !details.is_empty() {
+            body.append_pair("authorization_details", &authorization_details_param(details));
+        }
+        body
+    }
 
-        if let Some(scope) = scope {
-            body.append_pair("scope", scope);
+    /// Returns an authorization endpoint URI referencing pushed authorization request parameters.
+    ///
+    /// See [RFC 9126, section 4](https://tools.ietf.org/html/rfc9126#section-4).
+    pub fn pushed_auth_uri(&self, par: &PushedAuthorization) -> Url {
+        self.request_uri_auth_uri(par.request_uri())
+    }
+
+    /// Parses the authorization code from the redirect URI the user agent was sent to.
+    ///
+    /// Parameters are read from the query, or from the fragment if there is no query. The `state`
+    /// parameter must equal the state passed to `auth_uri`, protecting against cross-site request
+    /// forgery, so it should be unguessable, such as one from `random_state`. It is compared in
+    /// constant time. Authorization errors are returned as `ClientError::OAuth2`.
+    ///
+    /// See [RFC 6749, section 4.1.2](http://tools.ietf.org/html/rfc6749#section-4.1.2).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate inth_oauth2;
+    /// # extern crate url;
+    /// # use inth_oauth2::Client;
+    /// # use inth_oauth2::provider::google::Installed;
+    /// # fn main() {
+    /// # let client = Client::new(Installed, String::new(), String::new(), None);
+    /// let redirect = url::Url::parse("http://127.0.0.1:8080/?code=foo&state=bar").unwrap();
+    /// let code = client.parse_redirect(&redirect, "bar").unwrap();
+    /// assert_eq!("foo", code);
+    /// # }
+    /// ```
+    pub fn parse_redirect(&self, uri: &Url, state: &str) -> Result<String, ClientError> {
+        let pairs = match uri.fragment() {
+            Some(fragment) if uri.query().is_none() => form_urlencoded::parse(fragment.as_bytes()),
+            _ => uri.query_pairs(),
+        };
+        let json = Value::Object(
+            pairs.map(|(k, v)| (k.into_owned(), Value::String(v.into_owned()))).collect()
+        );
+
+        let valid_state = json.get("state")
+            .and_then(Value::as_str)
+            .is_some_and(|s| s.len() == state.len() && memcmp::eq(s.as_bytes(), state.as_bytes()));
+        if !valid_state {
+            return Err(ClientError::InvalidState);
         }
 
-        let json = self.post_token(http_client, body)?;
-        let token = P::Token::from_response_inherit(&json, &token)?;
-        Ok(token)
+        if let Ok(error) = OAuth2Error::from_response(&json) {
+            return Err(ClientError::from(error));
+        }
+
+        let code = json.get("code")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("code", "string"))?;
+        Ok(code.into())
     }
 
-    /// Ensures an access token is valid by refreshing it if necessary.
-    pub fn ensure_token(
+    fn post_token<H: HttpClient>(
         &self,
-        http_client: &reqwest::Client,
-        token: P::Token,
-    ) -> Result<P::Token, ClientError> {
-        if token.lifetime().expired() {
-            self.refresh_token(http_client, token, None)
-        } else {
-            Ok(token)
+        http_client: &H,
+        body: Serializer<String>,
+    ) -> Result<Value, ClientError> {
+        let json = self.post_form(http_client, self.provider.token_uri(), body)?;
+        Ok(self.confirm(json))
+    }
+
+    /// Posts a form, retrying once if the provider requires a new DPoP nonce.
+    fn post_form<H: HttpClient>(
+        &self,
+        http_client: &H,
+        uri: &Url,
+        mut body: Serializer<String>,
+    ) -> Result<Value, ClientError> {
+        let body = body.finish();
+        match self.post_form_once(http_client, uri, &body) {
+            Err(ClientError::OAuth2(ref err)) if self.retry_dpop_nonce(err) => {
+                self.post_form_once(http_client, uri, &body)
+            },
+            result => result,
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use url::Url;
-    use token::{Bearer, Static};
-    use provider::Provider;
-    use super::Client;
+    fn post_form_once<H: HttpClient>(
+        &self,
+        http_client: &H,
+        uri: &Url,
+        body: &str,
+    ) -> Result<Value, ClientError> {
+        let response = self.send_form(http_client, uri, Serializer::for_suffix(body.into(), 0))?;
+        let json = serde_json::from_slice(&response.body)?;
+        check_response(json)
+    }
 
-    struct Test {
-        auth_uri: Url,
-        token_uri: Url
+    fn send_form<H: HttpClient>(
+        &self,
+        http_client: &H,
+        uri: &Url,
+        body: Serializer<String>,
+    ) -> Result<HttpResponse, ClientError> {
+        let response = http_client.execute(self.form_request(uri, body)?)?;
+        if let Some(ref dpop) = self.dpop {
+            dpop.update_nonce(uri, &response);
+        }
+        Ok(response)
     }
-    impl Provider for Test {
-        type Lifetime = Static;
-        type Token = Bearer<Static>;
-        fn auth_uri(&self) -> &Url { &self.auth_uri }
-        fn token_uri(&self) -> &Url { &self.token_uri }
+
+    /// Returns true if a request failed because the provider requires a DPoP nonce, and should be
+    /// retried with the nonce from the response.
+    pub(crate) fn retry_dpop_nonce(&self, err: &OAuth2Error) -> bool {
+        self.dpop.is_some() && err.code == OAuth2ErrorCode::UseDpopNonce
     }
-    impl Test {
-        fn new() -> Self {
-            Test {
-                auth_uri: Url::parse("http://example.com/oauth2/auth").unwrap(),
-                token_uri: Url::parse("http://example.com/oauth2/token").unwrap()
+
+    /// Records the key a token response is bound to.
+    pub(crate) fn confirm(&self, json: Value) -> Value {
+        let json = self.auth.confirm(json);
+        match self.dpop {
+            Some(ref dpop) => dpop.confirm(json),
+            None => json,
+        }
+    }
+
+    /// Authenticates a form request to the provider.
+    ///
+    /// The audience of client assertions is the token endpoint, whichever endpoint the request
+    /// is to.
+    pub(crate) fn form_request(
+        &self,
+        uri: &Url,
+        mut body: Serializer<String>,
+    ) -> Result<HttpRequest, ClientError> {
+        let authorization = self.auth.authenticate(
+            &self.client_id,
+            &self.client_secret,
+            self.provider.token_uri(),
+            &mut body,
+        )?;
+
+        let mut headers = vec![
+            (String::from("Accept"), String::from("application/json")),
+            (String::from("Content-Type"), String::from("application/x-www-form-urlencoded")),
+        ];
+        if let Some(authorization) = authorization {
+            headers.push((String::from("Authorization"), authorization));
+        }
+        if let Some(ref dpop) = self.dpop {
+            if uri == self.provider.token_uri() {
+                headers.push((String::from("DPoP"), dpop.proof("POST", uri, None)?));
             }
         }
+
+        Ok(HttpRequest {
+            method: Method::Post,
+            url: uri.clone(),
+            headers,
+            body: body.finish().into_bytes(),
+        })
     }
 
-    #[test]
-    fn auth_uri() {
-        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
-        assert_eq!(
-            "http://example.com/oauth2/auth?response_type=code&client_id=foo",
-            client.auth_uri(None, None).as_str()
-        );
+    fn revoke<H: HttpClient>(
+        &self,
+        http_client: &H,
+        token: &str,
+        hint: TokenTypeHint,
+    ) -> Result<(), ClientError> {
+        let uri = self.revocation_uri()?;
+        let body = self.revocation_body(token, hint);
+
+        let response = self.send_form(http_client, uri, body)?;
+        if response.is_success() {
+            return Ok(());
+        }
+        Err(revocation_error(response.status, &response.body))
     }
 
-    #[test]
-    fn auth_uri_with_redirect_uri() {
-        let client = Client::new(
-            Test::new(),
-            String::from("foo"),
-            String::from("bar"),
-            Some(String::from("http://example.com/oauth2/callback")),
-        );
-        assert_eq!(
-            "http://example.com/oauth2/auth?response_type=code&client_id=foo&redirect_uri=http%3A%2F%2Fexample.com%2Foauth2%2Fcallback",
-            client.auth_uri(None, None).as_str()
-        );
+    pub(crate) fn revocation_uri(&self) -> Result<&Url, ClientError> {
+        self.provider.revocation_uri().ok_or(ClientError::UnsupportedEndpoint("revocation"))
     }
 
-    #[test]
-    fn auth_uri_with_scope() {
+    pub(crate) fn revocation_body(&self, token: &str, hint: TokenTypeHint) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("token", token);
+        body.append_pair("token_type_hint", hint.as_str());
+        body
+    }
+
+    /// Revokes an access token.
+    ///
+    /// See [RFC 7009, section 2.1](https://tools.ietf.org/html/rfc7009#section-2.1).
+    pub fn revoke_token<L: Lifetime, T: Token<L>, H: HttpClient>(
+        &self,
+        http_client: &H,
+        token: &T,
+    ) -> Result<(), ClientError> {
+        self.revoke(http_client, token.access_token(), TokenTypeHint::AccessToken)
+    }
+
+    /// Queries the provider for the state of a token.
+    ///
+    /// See [RFC 7662, section 2.1](https://tools.ietf.org/html/rfc7662#section-2.1).
+    pub fn introspect_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        token: &str,
+        hint: Option<TokenTypeHint>,
+    ) -> Result<Introspection, ClientError> {
+        let uri = self.introspection_uri()?;
+        let body = self.introspection_body(token, hint);
+        let json = self.post_form(http_client, uri, body)?;
+        let introspection = Introspection::from_response(&json)?;
+        Ok(introspection)
+    }
+
+    pub(crate) fn introspection_uri(&self) -> Result<&Url, ClientError> {
+        self.provider.introspection_uri()
+            .ok_or(ClientError::UnsupportedEndpoint("introspection"))
+    }
+
+    pub(crate) fn introspection_body(
+        &self,
+        token: &str,
+        hint: Option<TokenTypeHint>,
+    ) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("token", token);
+        if let Some(hint) = hint {
+            body.append_pair("token_type_hint", hint.as_str());
+        }
+        body
+    }
+
+    /// Requests an access token using an authorization code.
+    ///
+    /// See [RFC 6749, section 4.1.3](http://tools.ietf.org/html/rfc6749#section-4.1.3).
+    pub fn request_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        code: &str,
+    ) -> Result<P::Token, ClientError> {
+        self.request_token_with(http_client, code, None, &[])
+    }
+
+    /// Requests an access token using an authorization code and a PKCE code verifier.
+    ///
+    /// See [RFC 7636, section 4.5](https://tools.ietf.org/html/rfc7636#section-4.5).
+    pub fn request_token_with_verifier<H: HttpClient>(
+        &self,
+        http_client: &H,
+        code: &str,
+        verifier: &CodeVerifier,
+    ) -> Result<P::Token, ClientError> {
+        self.request_token_with(http_client, code, Some(verifier), &[])
+    }
+
+    /// Requests an access token using an authorization code, limited to a subset of the
+    /// authorization details granted.
+    ///
+    /// See [RFC 9396, section 6.1](https://tools.ietf.org/html/rfc9396#section-6.1).
+    pub fn request_token_with_details<H: HttpClient>(
+        &self,
+        http_client: &H,
+        code: &str,
+        verifier: Option<&CodeVerifier>,
+        details: &[AuthorizationDetail],
+    ) -> Result<P::Token, ClientError> {
+        self.request_token_with(http_client, code, verifier, details)
+    }
+
+    fn request_token_with<H: HttpClient>(
+        &self,
+        http_client: &H,
+        code: &str,
+        verifier: Option<&CodeVerifier>,
+        details: &[AuthorizationDetail],
+    ) -> Result<P::Token, ClientError> {
+        self.check_pkce(verifier.is_some())?;
+        let body = self.authorization_code_body(code, verifier, details);
+        let json = self.post_token(http_client, body)?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+
+    pub(crate) fn authorization_code_body(
+        &self,
+        code: &str,
+        verifier: Option<&CodeVerifier>,
+        details: &[AuthorizationDetail],
+    ) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("grant_type", "authorization_code");
+        body.append_pair("code", code);
+
+        if let Some(ref redirect_uri) = self.redirect_uri {
+            body.append_pair("redirect_uri", redirect_uri);
+        }
+        if let Some(verifier) = verifier {
+            body.append_pair("code_verifier", verifier.secret());
+        }
+        if !details.is_empty() {
+            body.append_pair("authorization_details", &authorization_details_param(details));
+        }
+        body
+    }
+
+    /// Requests an access token using the client credentials.
+    ///
+    /// See [RFC 6749, section 4.4.2](http://tools.ietf.org/html/rfc6749#section-4.4.2).
+    pub fn request_client_credentials_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        scope: Option<&str>,
+    ) -> Result<P::Token, ClientError> {
+        self.request_client_credentials_token_with_details(http_client, scope, &[])
+    }
+
+    /// Requests an access token using the client credentials, with authorization details in
+    /// addition to or instead of scope.
+    ///
+    /// See [RFC 9396, section 6](https://tools.ietf.org/html/rfc9396#section-6).
+    pub fn request_client_credentials_token_with_details<H: HttpClient>(
+        &self,
+        http_client: &H,
+        scope: Option<&str>,
+        details: &[AuthorizationDetail],
+    ) -> Result<P::Token, ClientError> {
+        let body = self.client_credentials_body(scope, details);
+        let json = self.post_token(http_client, body)?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+
+    pub(crate) fn client_credentials_body(
+        &self,
+        scope: Option<&str>,
+        details: &[AuthorizationDetail],
+    ) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("grant_type", "client_credentials");
+
+        if let Some(scope) = scope {
+            body.append_pair("scope", scope);
+        }
+        if !details.is_empty() {
+            body.append_pair("authorization_details", &authorization_details_param(details));
+        }
+        body
+    }
+
+    /// Requests an access token using the resource owner password credentials.
+    ///
+    /// See [RFC 6749, section 4.3.2](http://tools.ietf.org/html/rfc6749#section-4.3.2).
+    pub fn request_password_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        username: &str,
+        password: &str,
+        scope: Option<&str>,
+    ) -> Result<P::Token, ClientError> {
+        let body = self.password_body(username, password, scope);
+        let json = self.post_token(http_client, body)?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+
+    pub(crate) fn password_body(
+        &self,
+        username: &str,
+        password: &str,
+        scope: Option<&str>,
+    ) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("grant_type", "password");
+        body.append_pair("username", username);
+        body.append_pair("password", password);
+
+        if let Some(scope) = scope {
+            body.append_pair("scope", scope);
+        }
+        body
+    }
+
+    /// Requests an access token using a signed JWT assertion, such as from
+    /// `assertion::Assertion`.
+    ///
+    /// Client authentication is optional for this grant; clients without credentials should use
+    /// `ClientAuth::None` with an empty client ID.
+    ///
+    /// See [RFC 7523, section 2.1](https://tools.ietf.org/html/rfc7523#section-2.1).
+    pub fn request_assertion_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        assertion: &str,
+        scope: Option<&str>,
+    ) -> Result<P::Token, ClientError> {
+        let body = self.assertion_body(assertion, scope);
+        let json = self.post_token(http_client, body)?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+
+    pub(crate) fn assertion_body(&self, assertion: &str, scope: Option<&str>) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("grant_type", JWT_BEARER_GRANT_TYPE);
+        body.append_pair("assertion", assertion);
+
+        if let Some(scope) = scope {
+            body.append_pair("scope", scope);
+        }
+        body
+    }
+
+    /// Exchanges a subject token, and optionally an actor token, for a new token, such as one for
+    /// a downstream service acting on behalf of the subject.
+    ///
+    /// The issued token need not be an access token of the provider's token type, so the response
+    /// is parsed as an `ExchangedToken`.
+    ///
+    /// See [RFC 8693, section 2](https://tools.ietf.org/html/rfc8693#section-2).
+    pub fn request_token_exchange<H: HttpClient>(
+        &self,
+        http_client: &H,
+        exchange: &TokenExchange,
+    ) -> Result<ExchangedToken, ClientError> {
+        let json = self.post_token(http_client, exchange.body())?;
+        let token = ExchangedToken::from_response(&json)?;
+        Ok(token)
+    }
+
+    /// Requests device and user verification codes.
+    ///
+    /// See [RFC 8628, section 3.1](https://tools.ietf.org/html/rfc8628#section-3.1).
+    pub fn request_device_code<H: HttpClient>(
+        &self,
+        http_client: &H,
+        scope: Option<&str>,
+    ) -> Result<DeviceAuthorization, ClientError> {
+        let uri = self.device_authorization_uri()?;
+        let body = self.device_code_body(scope);
+        let json = self.post_form(http_client, uri, body)?;
+        let device = DeviceAuthorization::from_response(&json)?;
+        Ok(device)
+    }
+
+    pub(crate) fn device_authorization_uri(&self) -> Result<&Url, ClientError> {
+        self.provider.device_authorization_uri()
+            .ok_or(ClientError::UnsupportedEndpoint("device authorization"))
+    }
+
+    pub(crate) fn device_code_body(&self, scope: Option<&str>) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        if !self.auth.client_id_in_body() {
+            body.append_pair("client_id", &self.client_id);
+        }
+        if let Some(scope) = scope {
+            body.append_pair("scope", scope);
+        }
+        body
+    }
+
+    /// Requests an access token using a device code, once.
+    ///
+    /// Returns an `authorization_pending` or `slow_down` error while the user has not yet
+    /// completed authorization. See `poll_device_token` for a polling loop.
+    ///
+    /// See [RFC 8628, section 3.4](https://tools.ietf.org/html/rfc8628#section-3.4).
+    pub fn request_device_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        device: &DeviceAuthorization,
+    ) -> Result<P::Token, ClientError> {
+        let body = self.device_token_body(device);
+        let json = self.post_token(http_client, body)?;
+        let token = P::Token::from_response(&json)?;
+        Ok(token)
+    }
+
+    pub(crate) fn device_token_body(&self, device: &DeviceAuthorization) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("grant_type", "urn:ietf:params:oauth:grant-type:device_code");
+        body.append_pair("device_code", device.device_code());
+        if !self.auth.client_id_in_body() {
+            body.append_pair("client_id", &self.client_id);
+        }
+        body
+    }
+
+    /// Polls for an access token using a device code until the user completes authorization.
+    ///
+    /// Blocks the current thread, waiting the interval requested by the provider between
+    /// requests. Returns an `expired_token` error if the device code expires.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    pub fn poll_device_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        device: &DeviceAuthorization,
+    ) -> Result<P::Token, ClientError> {
+        let mut interval = device.interval();
+        loop {
+            if Utc::now() >= *device.expires() {
+                return Err(device_expired());
+            }
+
+            thread::sleep(interval);
+
+            match self.request_device_token(http_client, device) {
+                Err(err) => interval = next_interval(interval, &err).ok_or(err)?,
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<P> Client<P> where P: Provider, P::Token: Token<Refresh> {
+    /// Refreshes an access token.
+    ///
+    /// See [RFC 6749, section 6](http://tools.ietf.org/html/rfc6749#section-6).
+    pub fn refresh_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        token: P::Token,
+        scope: Option<&str>,
+    ) -> Result<P::Token, ClientError> {
+        let body = self.refresh_token_body(&token, scope);
+        let json = self.post_token(http_client, body)?;
+        let token = P::Token::from_response_inherit(&json, &token)?;
+        Ok(token)
+    }
+
+    /// Refreshes an access token with a scope, formatted with the provider's delimiter.
+    ///
+    /// The scope must not include any scope token not originally granted.
+    ///
+    /// See [RFC 6749, section 6](http://tools.ietf.org/html/rfc6749#section-6).
+    pub fn refresh_token_with_scope<H: HttpClient>(
+        &self,
+        http_client: &H,
+        token: P::Token,
+        scope: &Scope,
+    ) -> Result<P::Token, ClientError> {
+        self.refresh_token(http_client, token, Some(&self.format_scope(scope)))
+    }
+
+    pub(crate) fn refresh_token_body(
+        &self,
+        token: &P::Token,
+        scope: Option<&str>,
+    ) -> Serializer<String> {
+        let mut body = Serializer::new(String::new());
+        body.append_pair("grant_type", "refresh_token");
+        body.append_pair("refresh_token", token.lifetime().refresh_token());
+
+        if let Some(scope) = scope {
+            body.append_pair("scope", scope);
+        }
+        body
+    }
+
+    /// Revokes a refresh token.
+    ///
+    /// Depending on the provider, this may also revoke access tokens issued from the same
+    /// authorization grant.
+    ///
+    /// See [RFC 7009, section 2.1](https://tools.ietf.org/html/rfc7009#section-2.1).
+    pub fn revoke_refresh_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        token: &P::Token,
+    ) -> Result<(), ClientError> {
+        self.revoke(
+            http_client,
+            token.lifetime().refresh_token(),
+            TokenTypeHint::RefreshToken,
+        )
+    }
+
+    /// Ensures an access token is valid by refreshing it if necessary.
+    pub fn ensure_token<H: HttpClient>(
+        &self,
+        http_client: &H,
+        token: P::Token,
+    ) -> Result<P::Token, ClientError> {
+        if token.lifetime().expired() {
+            self.refresh_token(http_client, token, None)
+        } else {
+            Ok(token)
+        }
+    }
+
+    /// Refreshes an access token and saves the refreshed token to a store.
+    ///
+    /// If saving fails, the refreshed token is returned in `StoredRefreshError::Save` rather than
+    /// dropped, since the provider may already have invalidated the previous refresh token.
+    pub fn refresh_token_stored<H: HttpClient, S: TokenStore<P::Token>>(
+        &self,
+        http_client: &H,
+        store: &S,
+        key: &StoreKey,
+        token: P::Token,
+        scope: Option<&str>,
+    ) -> Result<P::Token, StoredRefreshError<P::Token>> {
+        let token = self.refresh_token(http_client, token, scope)
+            .map_err(StoredRefreshError::Refresh)?;
+        match store.save(key, &token) {
+            Ok(()) => Ok(token),
+            Err(err) => Err(StoredRefreshError::Save(token, err)),
+        }
+    }
+
+    /// Ensures an access token is valid by refreshing it if necessary, saving the refreshed token
+    /// to a store.
+    ///
+    /// See `refresh_token_stored`.
+    pub fn ensure_token_stored<H: HttpClient, S: TokenStore<P::Token>>(
+        &self,
+        http_client: &H,
+        store: &S,
+        key: &StoreKey,
+        token: P::Token,
+    ) -> Result<P::Token, StoredRefreshError<P::Token>> {
+        if token.lifetime().expired() {
+            self.refresh_token_stored(http_client, store, key, token, None)
+        } else {
+            Ok(token)
+        }
+    }
+}
+
+/// Generates a random `state` parameter value from 16 octets of entropy, to pass to `auth_uri`
+/// and check with `parse_redirect`.
+///
+/// Returns `ClientError::Crypto` if the random number generator fails.
+///
+/// See [RFC 6749, section 10.12](http://tools.ietf.org/html/rfc6749#section-10.12).
+pub fn random_state() -> Result<String, ClientError> {
+    Ok(random_jti()?)
+}
+
+/// Returns the response as an error if it is an OAuth 2.0 error.
+pub(crate) fn check_response(json: Value) -> Result<Value, ClientError> {
+    match OAuth2Error::from_response(&json) {
+        Ok(error) => Err(ClientError::from(error)),
+        Err(_) => Ok(json),
+    }
+}
+
+/// Returns the error for a failed revocation response, which is an OAuth 2.0 error if the body is
+/// one, and otherwise only its status.
+///
+/// See [RFC 7009, section 2.2.1](https://tools.ietf.org/html/rfc7009#section-2.2.1).
+pub(crate) fn revocation_error(status: u16, body: &[u8]) -> ClientError {
+    serde_json::from_slice(body).ok()
+        .and_then(|json| OAuth2Error::from_response(&json).ok())
+        .map_or(ClientError::Status(status), ClientError::from)
+}
+
+/// Serializes authorization details as the `authorization_details` request parameter.
+fn authorization_details_param(details: &[AuthorizationDetail]) -> String {
+    // Serializing strings and JSON values with string keys cannot fail.
+    serde_json::to_string(details).expect("authorization details serialize")
+}
+
+/// Returns the error for an expired device code.
+pub(crate) fn device_expired() -> ClientError {
+    ClientError::from(OAuth2Error {
+        code: OAuth2ErrorCode::ExpiredToken,
+        description: Some(String::from("device code expired")),
+        uri: None,
+    })
+}
+
+/// Returns the interval to wait before polling for a device token again after an error, or
+/// `None` if polling should stop.
+///
+/// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+pub(crate) fn next_interval(interval: Duration, err: &ClientError) -> Option<Duration> {
+    match *err {
+        ClientError::OAuth2(ref err) if err.code == OAuth2ErrorCode::AuthorizationPending => {
+            Some(interval)
+        },
+        ClientError::OAuth2(ref err) if err.code == OAuth2ErrorCode::SlowDown => {
+            Some(interval + Duration::from_secs(5))
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use url::Url;
+    use client::{ClientAuth, ClientError};
+    use client::device::DeviceAuthorization;
+    use client::exchange::{TokenExchange, TokenType};
+    use client::http::{HttpRequest, HttpResponse, MemoryClient, Method};
+    use client::response::{FromResponse, ParseError};
+    use dpop::DpopKey;
+    use error::{OAuth2Error, OAuth2ErrorCode};
+    use jwt::tests::{jwk, rsa_key};
+    use jwt::{Jwks, Jwt, SigningKey};
+    use pkce::{ChallengeMethod, CodeVerifier};
+    use token::{AuthorizationDetail, Bearer, Dpop, Refresh, Static, Token, TokenTypeHint};
+    use provider::Provider;
+    use scope::Scope;
+    use super::Client;
+
+    struct Test {
+        auth_uri: Url,
+        token_uri: Url
+    }
+    impl Provider for Test {
+        type Lifetime = Static;
+        type Token = Bearer<Static>;
+        fn auth_uri(&self) -> &Url { &self.auth_uri }
+        fn token_uri(&self) -> &Url { &self.token_uri }
+    }
+    impl Test {
+        fn new() -> Self {
+            Test {
+                auth_uri: Url::parse("http://example.com/oauth2/auth").unwrap(),
+                token_uri: Url::parse("http://example.com/oauth2/token").unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn format_scope() {
+        struct Commas(Test);
+        impl Provider for Commas {
+            type Lifetime = Static;
+            type Token = Bearer<Static>;
+            fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+            fn token_uri(&self) -> &Url { &self.0.token_uri }
+            fn scope_delimiter(&self) -> char { ',' }
+        }
+
+        let scope = Scope::from("a b");
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        assert_eq!("a b", client.format_scope(&scope));
+        let client = Client::new(Commas(Test::new()), String::from("foo"), String::from("bar"), None);
+        assert_eq!("a,b", client.format_scope(&scope));
+        assert_eq!(scope, client.parse_scope("a,b"));
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&scope=a%2Cb",
+            client.auth_uri_with_scope(&scope, None).as_str()
+        );
+    }
+
+    #[test]
+    fn granted_scope() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let requested = Scope::from("a b");
+
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
+        let token = Bearer::<Static>::from_response(&json).unwrap();
+        assert_eq!(requested, client.granted_scope(&token, &requested));
+        assert!(client.denied_scope(&token, &requested).is_empty());
+
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","scope":"a"}"#
+            .parse()
+            .unwrap();
+        let token = Bearer::<Static>::from_response(&json).unwrap();
+        assert_eq!(Some(Scope::from("a")), token.granted_scope());
+        assert_eq!(Scope::from("a"), client.granted_scope(&token, &requested));
+        assert_eq!(Scope::from("b"), client.denied_scope(&token, &requested));
+    }
+
+    #[test]
+    fn refresh_token_with_scope() {
+        struct RefreshTest(Test);
+        impl Provider for RefreshTest {
+            type Lifetime = Refresh;
+            type Token = Bearer<Refresh>;
+            fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+            fn token_uri(&self) -> &Url { &self.0.token_uri }
+        }
+
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":3600,"refresh_token":"bbbbbbbb","scope":"a b"}"#
+            .parse()
+            .unwrap();
+        let token = Bearer::<Refresh>::from_response(&json).unwrap();
+
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"cccccccc","expires_in":3600}"#);
+        let client = Client::new(RefreshTest(Test::new()), String::from("foo"), String::from("bar"), None);
+        let token = client.refresh_token_with_scope(&http, token, &Scope::from("a")).unwrap();
+        assert_eq!("cccccccc", token.access_token());
+        assert_eq!(
+            "grant_type=refresh_token&refresh_token=bbbbbbbb&scope=a",
+            form(&http.requests()[0])
+        );
+    }
+
+    #[test]
+    fn auth_uri() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo",
+            client.auth_uri(None, None).as_str()
+        );
+    }
+
+    #[test]
+    fn auth_uri_with_redirect_uri() {
+        let client = Client::new(
+            Test::new(),
+            String::from("foo"),
+            String::from("bar"),
+            Some(String::from("http://example.com/oauth2/callback")),
+        );
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&redirect_uri=http%3A%2F%2Fexample.com%2Foauth2%2Fcallback",
+            client.auth_uri(None, None).as_str()
+        );
+    }
+
+    #[test]
+    fn auth_uri_with_scope() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&scope=baz",
+            client.auth_uri(Some("baz"), None).as_str()
+        );
+    }
+
+    #[test]
+    fn auth_uri_with_state() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        assert_eq!(
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&state=baz",
+            client.auth_uri(None, Some("baz")).as_str()
+        );
+    }
+
+    #[test]
+    fn auth_uri_with_challenge() {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let verifier = CodeVerifier::new();
+        let challenge = verifier.challenge(ChallengeMethod::Plain);
+        assert_eq!(
+            format!(
+                "http://example.com/oauth2/auth?response_type=code&client_id=foo&code_challenge={}&code_challenge_method=plain",
+                verifier.secret(),
+            ),
+            client.auth_uri_with_challenge(None, None, &challenge).as_str()
+        );
+    }
+
+    #[test]
+    fn auth_uri_with_details() {
         let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let details = [AuthorizationDetail::new("payment_initiation")];
         assert_eq!(
-            "http://example.com/oauth2/auth?response_type=code&client_id=foo&scope=baz",
-            client.auth_uri(Some("baz"), None).as_str()
+            "http://example.com/oauth2/auth?response_type=code&client_id=foo&state=baz&authorization_details=%5B%7B%22type%22%3A%22payment_initiation%22%7D%5D",
+            client.auth_uri_with_details(None, Some("baz"), None, &details).as_str()
         );
     }
 
     #[test]
-    fn auth_uri_with_state() {
+    fn signed_auth_uri() {
+        let key = rsa_key();
+        let signing_key = SigningKey::from_private_key(key.clone()).unwrap().kid(String::from("1"));
+        let client = Client::new(
+            Test::new(),
+            String::from("foo"),
+            String::from("bar"),
+            Some(String::from("http://example.com/oauth2/callback")),
+        );
+        let details = [AuthorizationDetail::new("payment_initiation")];
+        let uri = client.signed_auth_uri(&signing_key, Some("baz"), Some("qux"), None, &details)
+            .unwrap();
+
+        let query: Vec<(String, String)> = uri.query_pairs().into_owned().collect();
+        assert_eq!(2, query.len());
+        assert_eq!(("client_id".into(), "foo".into()), query[0]);
+        assert_eq!("request", query[1].0);
+
+        let jwt = Jwt::decode(&query[1].1).unwrap();
+        jwt.verify(&Jwks { keys: vec![jwk(&key, "1")] }).unwrap();
+        assert_eq!(Some("oauth-authz-req+jwt"), jwt.header().typ.as_ref().map(|s| &s[..]));
+        let claims = jwt.claims();
+        assert_eq!(Some(&json!("foo")), claims.get("iss"));
+        assert_eq!(Some(&json!("http://example.com/oauth2/auth")), claims.get("aud"));
+        assert_eq!(Some(&json!("code")), claims.get("response_type"));
+        assert_eq!(Some(&json!("foo")), claims.get("client_id"));
+        assert_eq!(Some(&json!("http://example.com/oauth2/callback")), claims.get("redirect_uri"));
+        assert_eq!(Some(&json!("baz")), claims.get("scope"));
+        assert_eq!(Some(&json!("qux")), claims.get("state"));
+        assert_eq!(
+            Some(&json!([{"type": "payment_initiation"}])),
+            claims.get("authorization_details")
+        );
+        assert!(claims.contains_key("jti"));
+        assert!(claims.contains_key("exp"));
+    }
+
+    #[test]
+    fn request_uri_auth_uri() {
         let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
         assert_eq!(
-            "http://example.com/oauth2/auth?response_type=code&client_id=foo&state=baz",
-            client.auth_uri(None, Some("baz")).as_str()
+            "http://example.com/oauth2/auth?client_id=foo&request_uri=https%3A%2F%2Fexample.com%2Frequest.jwt",
+            client.request_uri_auth_uri("https://example.com/request.jwt").as_str()
+        );
+    }
+
+    struct ParTest(Test, Url);
+    impl Provider for ParTest {
+        type Lifetime = Static;
+        type Token = Bearer<Static>;
+        fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+        fn token_uri(&self) -> &Url { &self.0.token_uri }
+        fn pushed_authorization_request_uri(&self) -> Option<&Url> { Some(&self.1) }
+    }
+    impl ParTest {
+        fn new() -> Self {
+            ParTest(Test::new(), Url::parse("http://example.com/oauth2/par").unwrap())
+        }
+    }
+
+    #[test]
+    fn push_authorization_request() {
+        let http = MemoryClient::new();
+        http.push_json(201, r#"{"request_uri":"urn:example:aaaa","expires_in":60}"#);
+
+        let client = Client::new(
+            ParTest::new(),
+            String::from("foo"),
+            String::from("bar"),
+            Some(String::from("http://example.com/oauth2/callback")),
+        );
+        let par = client.push_authorization_request(&http, Some("baz"), Some("qux"), None, &[])
+            .unwrap();
+        assert_eq!("urn:example:aaaa", par.request_uri());
+        assert_eq!(
+            "http://example.com/oauth2/auth?client_id=foo&request_uri=urn%3Aexample%3Aaaaa",
+            client.pushed_auth_uri(&par).as_str()
+        );
+
+        let request = &http.requests()[0];
+        assert_eq!("http://example.com/oauth2/par", request.url.as_str());
+        assert_eq!(Some("Basic Zm9vOmJhcg=="), request.header("authorization"));
+        assert_eq!(
+            "response_type=code&client_id=foo&redirect_uri=http%3A%2F%2Fexample.com%2Foauth2%2Fcallback&scope=baz&state=qux",
+            form(request)
+        );
+    }
+
+    #[test]
+    fn push_authorization_request_client_secret_post() {
+        let http = MemoryClient::new();
+        http.push_json(201, r#"{"request_uri":"urn:example:aaaa","expires_in":60}"#);
+
+        let client = Client::new(ParTest::new(), String::from("foo"), String::from("bar"), None)
+            .with_auth(ClientAuth::ClientSecretPost);
+        client.push_authorization_request(&http, None, None, None, &[]).unwrap();
+        assert_eq!(
+            "response_type=code&client_id=foo&client_secret=bar",
+            form(&http.requests()[0])
+        );
+    }
+
+    #[test]
+    fn push_authorization_request_unsupported() {
+        let http = MemoryClient::new();
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        match client.push_authorization_request(&http, None, None, None, &[]) {
+            Err(ClientError::UnsupportedEndpoint("pushed authorization request")) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn random_state() {
+        let state = super::random_state().unwrap();
+        assert_eq!(22, state.len());
+        assert_ne!(state, super::random_state().unwrap());
+    }
+
+    fn parse_redirect(uri: &str, state: &str) -> Result<String, ClientError> {
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        client.parse_redirect(&Url::parse(uri).unwrap(), state)
+    }
+
+    #[test]
+    fn parse_redirect_query() {
+        let code = parse_redirect("http://localhost/callback?code=baz&state=qux", "qux");
+        assert_eq!("baz", code.unwrap());
+    }
+
+    #[test]
+    fn parse_redirect_fragment() {
+        let code = parse_redirect("http://localhost/callback#code=baz&state=qux", "qux");
+        assert_eq!("baz", code.unwrap());
+    }
+
+    #[test]
+    fn parse_redirect_missing_state() {
+        match parse_redirect("http://localhost/callback?code=baz", "qux") {
+            Err(ClientError::InvalidState) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn parse_redirect_mismatched_state() {
+        match parse_redirect("http://localhost/callback?code=baz&state=quux", "qux") {
+            Err(ClientError::InvalidState) => {},
+            result => panic!("{:?}", result),
+        }
+        match parse_redirect("http://localhost/callback?code=baz&state=quz", "qux") {
+            Err(ClientError::InvalidState) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn parse_redirect_error() {
+        let uri = "http://localhost/callback?error=access_denied&error_description=nope&state=qux";
+        match parse_redirect(uri, "qux") {
+            Err(ClientError::OAuth2(ref err)) => {
+                assert_eq!(OAuth2ErrorCode::AccessDenied, err.code);
+                assert_eq!(Some(String::from("nope")), err.description);
+            },
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn parse_redirect_without_code() {
+        match parse_redirect("http://localhost/callback?state=qux", "qux") {
+            Err(ClientError::Parse(ParseError::ExpectedFieldType("code", "string"))) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    fn form(request: &HttpRequest) -> &str {
+        ::std::str::from_utf8(&request.body).unwrap()
+    }
+
+    #[test]
+    fn request_token() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let token = client.request_token(&http, "baz").unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+
+        let request = &http.requests()[0];
+        assert_eq!(Method::Post, request.method);
+        assert_eq!("http://example.com/oauth2/token", request.url.as_str());
+        assert_eq!(Some("Basic Zm9vOmJhcg=="), request.header("authorization"));
+        assert_eq!(Some("application/x-www-form-urlencoded"), request.header("content-type"));
+        assert_eq!("grant_type=authorization_code&code=baz", form(request));
+    }
+
+    #[test]
+    fn request_token_error() {
+        let http = MemoryClient::new();
+        http.push_json(400, r#"{"error":"invalid_grant"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        match client.request_token(&http, "baz") {
+            Err(ClientError::OAuth2(ref err)) => assert_eq!(OAuth2ErrorCode::InvalidGrant, err.code),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn request_token_pkce_required() {
+        struct Pkce(Test);
+        impl Provider for Pkce {
+            type Lifetime = Static;
+            type Token = Bearer<Static>;
+            fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+            fn token_uri(&self) -> &Url { &self.0.token_uri }
+            fn pushed_authorization_request_uri(&self) -> Option<&Url> { Some(&self.0.token_uri) }
+            fn pkce_required(&self) -> bool { true }
+        }
+
+        let http = MemoryClient::new();
+        let client = Client::new(Pkce(Test::new()), String::from("foo"), String::from("bar"), None);
+        match client.request_token(&http, "baz") {
+            Err(ClientError::PkceRequired) => {},
+            result => panic!("{:?}", result),
+        }
+        match client.push_authorization_request(&http, None, None, None, &[]) {
+            Err(ClientError::PkceRequired) => {},
+            result => panic!("{:?}", result),
+        }
+        assert!(http.requests().is_empty());
+
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#);
+        let verifier = CodeVerifier::new();
+        client.request_token_with_verifier(&http, "baz", &verifier).unwrap();
+        assert_eq!(1, http.requests().len());
+    }
+
+    #[test]
+    fn request_token_client_secret_post() {
+        struct InBody(Test);
+        impl Provider for InBody {
+            type Lifetime = Static;
+            type Token = Bearer<Static>;
+            fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+            fn token_uri(&self) -> &Url { &self.0.token_uri }
+            fn client_auth(&self) -> ClientAuth { ClientAuth::ClientSecretPost }
+        }
+
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#);
+
+        let client = Client::new(InBody(Test::new()), String::from("foo"), String::from("bar"), None);
+        client.request_token(&http, "baz").unwrap();
+        assert_eq!(
+            "grant_type=authorization_code&code=baz&client_id=foo&client_secret=bar",
+            form(&http.requests()[0])
+        );
+    }
+
+    #[test]
+    fn request_client_credentials_token() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa","scope":"a b"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let token = client.request_client_credentials_token(&http, Some("a b")).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+
+        let request = &http.requests()[0];
+        assert_eq!("http://example.com/oauth2/token", request.url.as_str());
+        assert_eq!(Some("Basic Zm9vOmJhcg=="), request.header("authorization"));
+        assert_eq!("grant_type=client_credentials&scope=a+b", form(request));
+    }
+
+    #[test]
+    fn request_password_token() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let token = client.request_password_token(&http, "user", "p&ss word", Some("a")).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+
+        let request = &http.requests()[0];
+        assert_eq!("http://example.com/oauth2/token", request.url.as_str());
+        assert_eq!(Some("Basic Zm9vOmJhcg=="), request.header("authorization"));
+        assert_eq!(
+            "grant_type=password&username=user&password=p%26ss+word&scope=a",
+            form(request)
         );
     }
+
+    #[test]
+    fn request_client_credentials_token_with_details() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"
+            {
+                "token_type":"Bearer",
+                "access_token":"aaaaaaaa",
+                "authorization_details":[{"type":"payment_initiation","identifier":"1"}]
+            }
+        "#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let details = [AuthorizationDetail::new("payment_initiation")];
+        let token = client.request_client_credentials_token_with_details(&http, None, &details)
+            .unwrap();
+        assert_eq!(Some("1"), token.authorization_details()[0].identifier.as_ref().map(|s| &s[..]));
+        assert_eq!(
+            "grant_type=client_credentials&authorization_details=%5B%7B%22type%22%3A%22payment_initiation%22%7D%5D",
+            form(&http.requests()[0])
+        );
+    }
+
+    #[test]
+    fn request_token_client_secret_jwt() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None)
+            .with_auth(ClientAuth::ClientSecretJwt);
+        client.request_token(&http, "baz").unwrap();
+
+        let request = &http.requests()[0];
+        assert_eq!(None, request.header("authorization"));
+        let body = form(request);
+        assert!(body.contains("&client_id=foo&client_assertion_type="));
+        assert!(body.contains("&client_assertion="));
+        assert!(!body.contains("client_secret"));
+    }
+
+    #[test]
+    fn request_token_exchange() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"
+            {
+                "access_token":"cccccccc",
+                "issued_token_type":"urn:ietf:params:oauth:token-type:access_token",
+                "token_type":"Bearer",
+                "expires_in":60
+            }
+        "#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let exchange = TokenExchange::new("aaaaaaaa", TokenType::AccessToken)
+            .audience("backend");
+        let token = client.request_token_exchange(&http, &exchange).unwrap();
+        assert_eq!("cccccccc", token.access_token());
+        assert_eq!(&TokenType::AccessToken, token.issued_token_type());
+
+        let request = &http.requests()[0];
+        assert_eq!(Some("Basic Zm9vOmJhcg=="), request.header("authorization"));
+        assert_eq!(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Atoken-exchange\
+             &audience=backend\
+             &subject_token=aaaaaaaa\
+             &subject_token_type=urn%3Aietf%3Aparams%3Aoauth%3Atoken-type%3Aaccess_token",
+            form(request)
+        );
+    }
+
+    #[test]
+    fn request_token_exchange_dpop() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"
+            {
+                "access_token":"cccccccc",
+                "issued_token_type":"urn:ietf:params:oauth:token-type:access_token",
+                "token_type":"DPoP"
+            }
+        "#);
+
+        let key = DpopKey::generate().unwrap();
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None)
+            .with_dpop(key.clone());
+        let exchange = TokenExchange::new("aaaaaaaa", TokenType::AccessToken);
+        let token = client.request_token_exchange(&http, &exchange).unwrap();
+        assert!(token.confirmation().unwrap().matches_dpop_key(&key));
+    }
+
+    struct DpopTest(Test);
+    impl Provider for DpopTest {
+        type Lifetime = Static;
+        type Token = Dpop<Static>;
+        fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+        fn token_uri(&self) -> &Url { &self.0.token_uri }
+    }
+
+    fn dpop_proof(key: &DpopKey, request: &HttpRequest) -> Jwt {
+        let jwt = Jwt::decode(request.header("dpop").unwrap()).unwrap();
+        jwt.verify(&Jwks { keys: vec![key.jwk().clone()] }).unwrap();
+        jwt
+    }
+
+    #[test]
+    fn request_token_dpop() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"DPoP","access_token":"aaaaaaaa"}"#);
+
+        let key = DpopKey::generate().unwrap();
+        let client = Client::new(DpopTest(Test::new()), String::from("foo"), String::from("bar"), None)
+            .with_dpop(key.clone());
+        let token = client.request_token(&http, "baz").unwrap();
+        assert!(token.confirmation().unwrap().matches_dpop_key(&key));
+
+        let jwt = dpop_proof(&key, &http.requests()[0]);
+        assert_eq!(Some(&json!("POST")), jwt.claims().get("htm"));
+        assert_eq!(Some(&json!("http://example.com/oauth2/token")), jwt.claims().get("htu"));
+    }
+
+    #[test]
+    fn request_token_dpop_nonce() {
+        let http = MemoryClient::new();
+        http.push(HttpResponse {
+            status: 400,
+            headers: vec![(String::from("DPoP-Nonce"), String::from("n-1"))],
+            body: br#"{"error":"use_dpop_nonce"}"#.to_vec(),
+        });
+        http.push_json(200, r#"{"token_type":"DPoP","access_token":"aaaaaaaa"}"#);
+
+        let key = DpopKey::generate().unwrap();
+        let client = Client::new(DpopTest(Test::new()), String::from("foo"), String::from("bar"), None)
+            .with_dpop(key.clone());
+        client.request_token(&http, "baz").unwrap();
+
+        let requests = http.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!(None, dpop_proof(&key, &requests[0]).claims().get("nonce"));
+        assert_eq!(Some(&json!("n-1")), dpop_proof(&key, &requests[1]).claims().get("nonce"));
+        assert_eq!(form(&requests[0]), form(&requests[1]));
+    }
+
+    #[test]
+    fn request_token_use_dpop_nonce_without_key() {
+        let http = MemoryClient::new();
+        http.push_json(400, r#"{"error":"use_dpop_nonce"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        match client.request_token(&http, "baz") {
+            Err(ClientError::OAuth2(ref err)) => assert_eq!(OAuth2ErrorCode::UseDpopNonce, err.code),
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(1, http.requests().len());
+    }
+
+    struct RevokeTest(Test, Url);
+    impl Provider for RevokeTest {
+        type Lifetime = Refresh;
+        type Token = Bearer<Refresh>;
+        fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+        fn token_uri(&self) -> &Url { &self.0.token_uri }
+        fn revocation_uri(&self) -> Option<&Url> { Some(&self.1) }
+    }
+    impl RevokeTest {
+        fn client() -> Client<Self> {
+            let provider = RevokeTest(Test::new(), Url::parse("http://example.com/oauth2/revoke").unwrap());
+            Client::new(provider, String::from("foo"), String::from("bar"), None)
+        }
+    }
+
+    fn refresh_token() -> Bearer<Refresh> {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":3600,"refresh_token":"bbbbbbbb"}"#
+            .parse()
+            .unwrap();
+        Bearer::from_response(&json).unwrap()
+    }
+
+    #[test]
+    fn revoke_token() {
+        let http = MemoryClient::new();
+        http.push(HttpResponse { status: 200, headers: vec![], body: vec![] });
+
+        RevokeTest::client().revoke_token(&http, &refresh_token()).unwrap();
+
+        let request = &http.requests()[0];
+        assert_eq!(Method::Post, request.method);
+        assert_eq!("http://example.com/oauth2/revoke", request.url.as_str());
+        assert_eq!(Some("Basic Zm9vOmJhcg=="), request.header("authorization"));
+        assert_eq!("token=aaaaaaaa&token_type_hint=access_token", form(request));
+    }
+
+    #[test]
+    fn revoke_refresh_token() {
+        let http = MemoryClient::new();
+        http.push(HttpResponse { status: 200, headers: vec![], body: vec![] });
+
+        RevokeTest::client().revoke_refresh_token(&http, &refresh_token()).unwrap();
+        assert_eq!(
+            "token=bbbbbbbb&token_type_hint=refresh_token",
+            form(&http.requests()[0])
+        );
+    }
+
+    #[test]
+    fn revoke_token_error() {
+        let http = MemoryClient::new();
+        http.push_json(400, r#"{"error":"invalid_request"}"#);
+
+        match RevokeTest::client().revoke_token(&http, &refresh_token()) {
+            Err(ClientError::OAuth2(ref err)) => assert_eq!(OAuth2ErrorCode::InvalidRequest, err.code),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn revoke_token_status_error() {
+        let http = MemoryClient::new();
+        http.push(HttpResponse {
+            status: 503,
+            headers: vec![(String::from("Content-Type"), String::from("text/html"))],
+            body: b"<h1>Service Unavailable</h1>".to_vec(),
+        });
+
+        match RevokeTest::client().revoke_token(&http, &refresh_token()) {
+            Err(ClientError::Status(503)) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn revoke_token_unsupported() {
+        let http = MemoryClient::new();
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
+        match client.revoke_token(&http, &Bearer::<Static>::from_response(&json).unwrap()) {
+            Err(ClientError::UnsupportedEndpoint("revocation")) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    struct IntrospectTest(Test, Url);
+    impl Provider for IntrospectTest {
+        type Lifetime = Static;
+        type Token = Bearer<Static>;
+        fn auth_uri(&self) -> &Url { &self.0.auth_uri }
+        fn token_uri(&self) -> &Url { &self.0.token_uri }
+        fn introspection_uri(&self) -> Option<&Url> { Some(&self.1) }
+    }
+    impl IntrospectTest {
+        fn client() -> Client<Self> {
+            let provider = IntrospectTest(Test::new(), Url::parse("http://example.com/oauth2/introspect").unwrap());
+            Client::new(provider, String::from("foo"), String::from("bar"), None)
+        }
+    }
+
+    #[test]
+    fn introspect_token() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"active":true,"scope":"a b","client_id":"foo","username":"user"}"#);
+
+        let client = IntrospectTest::client();
+        let introspection = client
+            .introspect_token(&http, "aaaaaaaa", Some(TokenTypeHint::AccessToken))
+            .unwrap();
+        assert!(introspection.active);
+        assert_eq!(Some(String::from("a b")), introspection.scope);
+        assert_eq!(Some(String::from("user")), introspection.username);
+
+        let request = &http.requests()[0];
+        assert_eq!(Method::Post, request.method);
+        assert_eq!("http://example.com/oauth2/introspect", request.url.as_str());
+        assert_eq!(Some("Basic Zm9vOmJhcg=="), request.header("authorization"));
+        assert_eq!("token=aaaaaaaa&token_type_hint=access_token", form(request));
+    }
+
+    #[test]
+    fn introspect_token_inactive() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"active":false}"#);
+
+        let introspection = IntrospectTest::client().introspect_token(&http, "aaaaaaaa", None).unwrap();
+        assert!(!introspection.active);
+        assert_eq!(None, introspection.client_id);
+        assert_eq!("token=aaaaaaaa", form(&http.requests()[0]));
+    }
+
+    #[test]
+    fn introspect_token_unsupported() {
+        let http = MemoryClient::new();
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        match client.introspect_token(&http, "aaaaaaaa", None) {
+            Err(ClientError::UnsupportedEndpoint("introspection")) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    fn device(expires_in: i64) -> DeviceAuthorization {
+        let json = json!({
+            "device_code": "dddddddd",
+            "user_code": "WDJB-MJHT",
+            "verification_uri": "https://example.com/device",
+            "expires_in": expires_in,
+            "interval": 0,
+        });
+        DeviceAuthorization::from_response(&json).unwrap()
+    }
+
+    #[test]
+    fn poll_device_token() {
+        let http = MemoryClient::new();
+        http.push_json(400, r#"{"error":"authorization_pending"}"#);
+        http.push_json(400, r#"{"error":"authorization_pending"}"#);
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        let token = client.poll_device_token(&http, &device(1800)).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+
+        let requests = http.requests();
+        assert_eq!(3, requests.len());
+        assert_eq!(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code&device_code=dddddddd&client_id=foo",
+            form(&requests[2])
+        );
+    }
+
+    #[test]
+    fn next_interval() {
+        let error = |code| ClientError::from(OAuth2Error { code, description: None, uri: None });
+        let interval = Duration::from_secs(5);
+        assert_eq!(
+            Some(interval),
+            super::next_interval(interval, &error(OAuth2ErrorCode::AuthorizationPending))
+        );
+        assert_eq!(
+            Some(Duration::from_secs(10)),
+            super::next_interval(interval, &error(OAuth2ErrorCode::SlowDown))
+        );
+        assert_eq!(None, super::next_interval(interval, &error(OAuth2ErrorCode::AccessDenied)));
+        assert_eq!(None, super::next_interval(interval, &ClientError::Status(500)));
+    }
+
+    #[test]
+    fn poll_device_token_expired_token() {
+        let http = MemoryClient::new();
+        http.push_json(400, r#"{"error":"authorization_pending"}"#);
+        http.push_json(400, r#"{"error":"expired_token"}"#);
+
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        match client.poll_device_token(&http, &device(1800)) {
+            Err(ClientError::OAuth2(ref err)) => assert_eq!(OAuth2ErrorCode::ExpiredToken, err.code),
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(2, http.requests().len());
+    }
+
+    #[test]
+    fn poll_device_token_expired_device_code() {
+        let http = MemoryClient::new();
+        let client = Client::new(Test::new(), String::from("foo"), String::from("bar"), None);
+        match client.poll_device_token(&http, &device(0)) {
+            Err(ClientError::OAuth2(ref err)) => assert_eq!(OAuth2ErrorCode::ExpiredToken, err.code),
+            result => panic!("{:?}", result),
+        }
+        assert!(http.requests().is_empty());
+    }
 }
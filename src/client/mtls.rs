@@ -0,0 +1,286 @@
+//! Mutual-TLS client authentication.
+//!
+//! See [RFC 8705](https://tools.ietf.org/html/rfc8705).
+
+use std::fmt;
+use std::io;
+
+use base64;
+use openssl::hash::{MessageDigest, hash};
+use openssl::pkey::{PKey, PKeyRef, Private};
+use openssl::x509::{X509, X509Ref};
+
+#[cfg(feature = "reqwest-client")]
+use openssl::pkcs12::Pkcs12;
+#[cfg(feature = "reqwest-client")]
+use reqwest;
+
+use client::ClientError;
+
+/// A client certificate and its private key, presented to the provider during the TLS
+/// handshake.
+///
+/// The certificate is presented by the HTTP client, which must be configured with it, for
+/// example using `identity` with `reqwest`. Tokens issued to a client authenticating with a
+/// certificate are bound to it, and record its thumbprint.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate inth_oauth2;
+/// # extern crate reqwest;
+/// use inth_oauth2::{Client, ClientAuth};
+/// use inth_oauth2::client::mtls::ClientCertificate;
+/// use inth_oauth2::provider::google::Web;
+///
+/// # fn main() {
+/// let cert = std::fs::read("client.pem").unwrap();
+/// let key = std::fs::read("client.key").unwrap();
+/// let cert = ClientCertificate::from_pem(&cert, &key).unwrap();
+///
+/// let http = reqwest::Client::builder()
+///     .identity(cert.identity().unwrap())
+///     .build()
+///     .unwrap();
+/// let client = Client::new(Web, String::from("CLIENT_ID"), String::new(), None)
+///     .with_auth(ClientAuth::TlsClientAuth(cert));
+/// let token = client.request_token(&http, "CODE").unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ClientCertificate {
+    cert: X509,
+    key: PKey<Private>,
+    thumbprint: String,
+}
+
+impl ClientCertificate {
+    /// Creates a client certificate from a certificate and its private key.
+    pub fn new(cert: X509, key: PKey<Private>) -> Result<Self, ClientError> {
+        if !cert.public_key()?.public_eq(&key) {
+            return Err(ClientError::from(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "private key does not match certificate",
+            )));
+        }
+        let thumbprint = thumbprint(&cert)?;
+        Ok(ClientCertificate { cert, key, thumbprint })
+    }
+
+    /// Creates a client certificate from PEM-encoded certificate and private key.
+    pub fn from_pem(cert: &[u8], key: &[u8]) -> Result<Self, ClientError> {
+        ClientCertificate::new(X509::from_pem(cert)?, PKey::private_key_from_pem(key)?)
+    }
+
+    /// Returns the certificate.
+    pub fn certificate(&self) -> &X509Ref { &self.cert }
+
+    /// Returns the private key, for configuring HTTP clients other than `reqwest`.
+    pub fn private_key(&self) -> &PKeyRef<Private> { &self.key }
+
+    /// Returns the `x5t#S256` thumbprint of the certificate.
+    pub fn thumbprint(&self) -> &str { &self.thumbprint }
+
+    /// Returns a `reqwest` identity presenting the certificate.
+    #[cfg(feature = "reqwest-client")]
+    pub fn identity(&self) -> Result<reqwest::Identity, ClientError> {
+        let pkcs12 = Pkcs12::builder()
+            .name("client")
+            .pkey(&self.key)
+            .cert(&self.cert)
+            .build2("")?;
+        Ok(reqwest::Identity::from_pkcs12_der(&pkcs12.to_der()?, "")?)
+    }
+}
+
+impl PartialEq for ClientCertificate {
+    fn eq(&self, other: &Self) -> bool {
+        self.thumbprint == other.thumbprint
+    }
+}
+
+impl Eq for ClientCertificate {}
+
+impl fmt::Debug for ClientCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("ClientCertificate")
+            .field("thumbprint", &self.thumbprint)
+            .finish()
+    }
+}
+
+/// Returns the `x5t#S256` thumbprint of a certificate, the base64url-encoded SHA-256 digest of
+/// its DER encoding.
+///
+/// See [RFC 8705, section 3.1](https://tools.ietf.org/html/rfc8705#section-3.1).
+pub fn thumbprint(cert: &X509Ref) -> Result<String, ClientError> {
+    let digest = hash(MessageDigest::sha256(), &cert.to_der()?)?;
+    Ok(base64::encode_config(&digest, base64::URL_SAFE_NO_PAD))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::{BigNum, MsbOption};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509, X509NameBuilder};
+
+    use jwt::tests::{ec_key, rsa_key};
+    use super::{ClientCertificate, thumbprint};
+
+    /// Creates a self-signed certificate for `localhost` and 127.0.0.1.
+    pub fn self_signed(key: &PKey<Private>, cn: &str) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", cn).unwrap();
+        let name = name.build();
+
+        let mut serial = BigNum::new().unwrap();
+        serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_serial_number(&serial.to_asn1_integer().unwrap()).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .ip("127.0.0.1")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    pub fn client_certificate() -> ClientCertificate {
+        let key = ec_key();
+        ClientCertificate::new(self_signed(&key, "client"), key).unwrap()
+    }
+
+    #[test]
+    fn thumbprint_format() {
+        let cert = client_certificate();
+        assert_eq!(43, cert.thumbprint().len());
+        assert!(!cert.thumbprint().contains('='));
+        assert_eq!(cert.thumbprint(), thumbprint(cert.certificate()).unwrap());
+    }
+
+    #[test]
+    fn from_pem() {
+        let key = ec_key();
+        let cert = self_signed(&key, "client");
+        let pem = ClientCertificate::from_pem(
+            &cert.to_pem().unwrap(),
+            &key.private_key_to_pem_pkcs8().unwrap(),
+        ).unwrap();
+        assert!(pem.private_key().public_eq(&key));
+        assert_eq!(ClientCertificate::new(cert, key).unwrap(), pem);
+    }
+
+    #[test]
+    fn mismatched_key() {
+        let cert = self_signed(&ec_key(), "client");
+        assert!(ClientCertificate::new(cert, rsa_key()).is_err());
+    }
+
+    #[test]
+    fn debug_redacted() {
+        let debug = format!("{:?}", client_certificate());
+        assert!(debug.starts_with("ClientCertificate { thumbprint: "));
+        assert!(!debug.contains("PRIVATE"));
+    }
+
+    #[cfg(feature = "reqwest-client")]
+    #[test]
+    fn request_token_over_mutual_tls() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+        use reqwest;
+        use url::Url;
+
+        use client::{Client, ClientAuth};
+        use provider::Provider;
+        use token::{Bearer, Static, Token};
+
+        struct Local(Url, Url);
+        impl Provider for Local {
+            type Lifetime = Static;
+            type Token = Bearer<Static>;
+            fn auth_uri(&self) -> &Url { &self.0 }
+            fn token_uri(&self) -> &Url { &self.1 }
+        }
+
+        let server_key = ec_key();
+        let server_cert = self_signed(&server_key, "localhost");
+        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&server_key).unwrap();
+        acceptor.set_certificate(&server_cert).unwrap();
+        // The client certificate is self-signed, and is checked by its thumbprint instead.
+        acceptor.set_verify_callback(
+            SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT,
+            |_, _| true,
+        );
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let stream = listener.accept().unwrap().0;
+            let stream = acceptor.accept(stream).unwrap();
+            let peer = thumbprint(&stream.ssl().peer_certificate().unwrap()).unwrap();
+
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" { break; }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#;
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                json.len(),
+                json,
+            ).unwrap();
+            (peer, String::from_utf8(body).unwrap())
+        });
+
+        let cert = client_certificate();
+        let http = reqwest::Client::builder()
+            .identity(cert.identity().unwrap())
+            .add_root_certificate(reqwest::Certificate::from_der(&server_cert.to_der().unwrap()).unwrap())
+            .build()
+            .unwrap();
+        let base = format!("https://127.0.0.1:{}", port);
+        let provider = Local(
+            Url::parse(&format!("{}/auth", base)).unwrap(),
+            Url::parse(&format!("{}/token", base)).unwrap(),
+        );
+        let client = Client::new(provider, String::from("foo"), String::from("bar"), None)
+            .with_auth(ClientAuth::TlsClientAuth(cert.clone()));
+
+        let token = client.request_token(&http, "baz").unwrap();
+        let (peer, body) = server.join().unwrap();
+        assert_eq!(cert.thumbprint(), peer);
+        assert_eq!("grant_type=authorization_code&code=baz&client_id=foo", body);
+        assert_eq!(
+            Some(cert.thumbprint()),
+            token.confirmation().and_then(|cnf| cnf.x5t_s256.as_ref()).map(|s| &s[..])
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Pushed authorization requests.
+//!
+//! See [RFC 9126](https://tools.ietf.org/html/rfc9126).
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+
+use client::response::{FromResponse, ParseError};
+
+/// Pushed authorization response.
+///
+/// See [RFC 9126, section 2.2](https://tools.ietf.org/html/rfc9126#section-2.2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushedAuthorization {
+    request_uri: String,
+    expires: DateTime<Utc>,
+}
+
+impl PushedAuthorization {
+    /// Returns the request URI referencing the pushed parameters.
+    pub fn request_uri(&self) -> &str { &self.request_uri }
+
+    /// Returns the expiry time of the request URI.
+    pub fn expires(&self) -> &DateTime<Utc> { &self.expires }
+
+    /// Returns true if the request URI has expired.
+    pub fn expired(&self) -> bool { self.expires < Utc::now() }
+}
+
+impl FromResponse for PushedAuthorization {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let request_uri = obj.get("request_uri")
+            .and_then(Value::as_str)
+            .ok_or(ParseError::ExpectedFieldType("request_uri", "string"))?;
+        let expires_in = obj.get("expires_in")
+            .and_then(Value::as_i64)
+            .ok_or(ParseError::ExpectedFieldType("expires_in", "i64"))?;
+
+        Ok(PushedAuthorization {
+            request_uri: request_uri.into(),
+            expires: Utc::now() + Duration::seconds(expires_in),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use client::response::{FromResponse, ParseError};
+    use super::PushedAuthorization;
+
+    #[test]
+    fn from_response() {
+        let json = r#"
+            {
+                "request_uri":"urn:ietf:params:oauth:request_uri:6esc_11ACC5bwc014ltc14eY22c",
+                "expires_in":60
+            }
+        "#.parse().unwrap();
+        let par = PushedAuthorization::from_response(&json).unwrap();
+        assert_eq!(
+            "urn:ietf:params:oauth:request_uri:6esc_11ACC5bwc014ltc14eY22c",
+            par.request_uri()
+        );
+        assert!(par.expires() > &Utc::now());
+        assert!(par.expires() <= &(Utc::now() + Duration::seconds(60)));
+        assert!(!par.expired());
+    }
+
+    #[test]
+    fn from_response_without_expires_in() {
+        let json = r#"{"request_uri":"urn:example"}"#.parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("expires_in", "i64"),
+            PushedAuthorization::from_response(&json).unwrap_err()
+        );
+    }
+}
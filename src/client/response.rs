@@ -3,7 +3,9 @@
 use std::error::Error;
 use std::fmt;
 
-use serde_json::Value;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde_json::{self, Map, Value};
 
 /// Response parsing.
 pub trait FromResponse: Sized {
@@ -54,3 +56,71 @@ impl fmt::Display for ParseError {
 impl Error for ParseError {
     fn description(&self) -> &str { "response parse error" }
 }
+
+/// Parses an optional string field.
+pub(crate) fn optional_string(
+    obj: &Map<String, Value>,
+    key: &'static str,
+) -> Result<Option<String>, ParseError> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_str()
+            .map(|s| Some(s.to_owned()))
+            .ok_or(ParseError::ExpectedFieldType(key, "string")),
+    }
+}
+
+/// Parses an optional field of seconds since the epoch.
+pub(crate) fn optional_timestamp(
+    obj: &Map<String, Value>,
+    key: &'static str,
+) -> Result<Option<DateTime<Utc>>, ParseError> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_i64()
+            .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+            .map(Some)
+            .ok_or(ParseError::ExpectedFieldType(key, "i64")),
+    }
+}
+
+/// Parses an optional object field.
+pub(crate) fn optional_object<T: DeserializeOwned>(
+    obj: &Map<String, Value>,
+    key: &'static str,
+) -> Result<Option<T>, ParseError> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|_| ParseError::ExpectedFieldType(key, "object")),
+    }
+}
+
+/// Parses an optional array field, defaulting to empty.
+pub(crate) fn optional_array<T: DeserializeOwned>(
+    obj: &Map<String, Value>,
+    key: &'static str,
+) -> Result<Vec<T>, ParseError> {
+    match obj.get(key) {
+        None => Ok(Vec::new()),
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|_| ParseError::ExpectedFieldType(key, "array")),
+    }
+}
+
+/// Parses an optional field which is either a string or an array of strings.
+pub(crate) fn string_or_array(
+    obj: &Map<String, Value>,
+    key: &'static str,
+) -> Result<Vec<String>, ParseError> {
+    match obj.get(key) {
+        None => Ok(Vec::new()),
+        Some(Value::String(s)) => Ok(vec![s.clone()]),
+        Some(Value::Array(values)) => values.iter()
+            .map(|value| value.as_str().map(Into::into))
+            .collect::<Option<_>>()
+            .ok_or(ParseError::ExpectedFieldType(key, "array of strings")),
+        Some(_) => Err(ParseError::ExpectedFieldType(key, "string")),
+    }
+}
@@ -0,0 +1,346 @@
+//! Demonstrating Proof of Possession.
+//!
+//! A DPoP key signs a proof JWT for each request, binding tokens issued to the client to the
+//! key, so that a stolen token cannot be used without it.
+//!
+//! See [RFC 9449](https://tools.ietf.org/html/rfc9449).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use base64;
+use chrono::Utc;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::sha::sha256;
+use serde_json::{self, Map, Value};
+use url::Url;
+
+use client::ClientError;
+use client::http::{HttpClient, HttpRequest, HttpResponse};
+use jwt::{Algorithm, Jwk, JwtError, SigningKey, random_jti};
+
+/// A key proving possession of DPoP-bound tokens.
+///
+/// Nonces provided by servers are remembered per origin and included in later proofs. Clones
+/// share remembered nonces.
+///
+/// Tokens issued with a DPoP key have the `DPoP` token type, so the provider's token type must be
+/// `token::Dpop`. Parsing them as `token::Bearer` fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate inth_oauth2;
+/// # extern crate reqwest;
+/// # extern crate url;
+/// use inth_oauth2::{Client, Token};
+/// use inth_oauth2::client::http::HttpRequest;
+/// use inth_oauth2::dpop::DpopKey;
+/// use inth_oauth2::provider::Provider;
+/// use inth_oauth2::token::{Dpop, Refresh};
+/// use url::Url;
+///
+/// struct Example {
+///     auth_uri: Url,
+///     token_uri: Url,
+/// }
+///
+/// impl Provider for Example {
+///     type Lifetime = Refresh;
+///     type Token = Dpop<Refresh>;
+///     fn auth_uri(&self) -> &Url { &self.auth_uri }
+///     fn token_uri(&self) -> &Url { &self.token_uri }
+/// }
+///
+/// # fn main() {
+/// let provider = Example {
+///     auth_uri: Url::parse("https://example.com/authorize").unwrap(),
+///     token_uri: Url::parse("https://example.com/token").unwrap(),
+/// };
+/// let http = reqwest::Client::new();
+/// let key = DpopKey::generate().unwrap();
+/// let client = Client::new(provider, String::from("CLIENT_ID"), String::new(), None)
+///     .with_dpop(key.clone());
+/// let token = client.request_token(&http, "CODE").unwrap();
+///
+/// let request = HttpRequest::get("https://example.com/resource".parse().unwrap());
+/// let response = key.execute(&http, request, token.access_token()).unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DpopKey {
+    key: SigningKey,
+    jwk: Jwk,
+    thumbprint: String,
+    nonces: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl DpopKey {
+    /// Creates a DPoP key from an RS256 or ES256 signing key.
+    pub fn new(key: SigningKey) -> Result<Self, JwtError> {
+        if key.algorithm() == Algorithm::HS256 {
+            return Err(JwtError::InvalidKey);
+        }
+        let mut jwk = key.public_jwk()?;
+        jwk.kid = None;
+        jwk.alg = None;
+        jwk.use_ = None;
+        let thumbprint = jwk.thumbprint()?;
+        Ok(DpopKey {
+            key,
+            jwk,
+            thumbprint,
+            nonces: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Generates a P-256 DPoP key.
+    pub fn generate() -> Result<Self, JwtError> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+        DpopKey::new(SigningKey::from_private_key(key)?)
+    }
+
+    /// Returns the public JWK of the key.
+    pub fn jwk(&self) -> &Jwk { &self.jwk }
+
+    /// Returns the JWK thumbprint of the key, the `jkt` confirmation of bound tokens.
+    ///
+    /// See [RFC 9449, section 6.1](https://tools.ietf.org/html/rfc9449#section-6.1).
+    pub fn thumbprint(&self) -> &str { &self.thumbprint }
+
+    /// Returns the nonce last provided by the origin of a URI.
+    pub fn nonce(&self, uri: &Url) -> Option<String> {
+        self.nonces.lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(&uri.origin().ascii_serialization())
+            .cloned()
+    }
+
+    /// Remembers a nonce provided by the origin of a URI.
+    pub fn set_nonce(&self, uri: &Url, nonce: &str) {
+        self.nonces.lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(uri.origin().ascii_serialization(), nonce.into());
+    }
+
+    /// Remembers the nonce provided in the `DPoP-Nonce` header of a response, returning true if
+    /// it is new.
+    ///
+    /// See [RFC 9449, section 8](https://tools.ietf.org/html/rfc9449#section-8).
+    pub fn update_nonce(&self, uri: &Url, response: &HttpResponse) -> bool {
+        match response.header("DPoP-Nonce") {
+            Some(nonce) if self.nonce(uri).as_ref().map(|s| &s[..]) != Some(nonce) => {
+                self.set_nonce(uri, nonce);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Signs a proof for a request, including the hash of the access token for requests to
+    /// resources.
+    ///
+    /// See [RFC 9449, section 4.2](https://tools.ietf.org/html/rfc9449#section-4.2).
+    pub fn proof(
+        &self,
+        method: &str,
+        uri: &Url,
+        access_token: Option<&str>,
+    ) -> Result<String, ClientError> {
+        let mut htu = uri.clone();
+        htu.set_query(None);
+        htu.set_fragment(None);
+
+        let mut claims = Map::new();
+        claims.insert(String::from("jti"), Value::from(random_jti()?));
+        claims.insert(String::from("htm"), Value::from(method));
+        claims.insert(String::from("htu"), Value::from(htu.as_str()));
+        claims.insert(String::from("iat"), Value::from(Utc::now().timestamp()));
+        if let Some(access_token) = access_token {
+            let ath = sha256(access_token.as_bytes());
+            claims.insert(
+                String::from("ath"),
+                Value::from(base64::encode_config(&ath, base64::URL_SAFE_NO_PAD)),
+            );
+        }
+        if let Some(nonce) = self.nonce(uri) {
+            claims.insert(String::from("nonce"), Value::from(nonce));
+        }
+
+        let mut header = Map::new();
+        header.insert(String::from("typ"), Value::from("dpop+jwt"));
+        header.insert(String::from("jwk"), serde_json::to_value(&self.jwk)?);
+        Ok(self.key.sign_with_header(header, &Value::Object(claims))?)
+    }
+
+    /// Authorizes a request to a resource with a DPoP-bound access token.
+    ///
+    /// See [RFC 9449, section 7.1](https://tools.ietf.org/html/rfc9449#section-7.1).
+    pub fn authorize(
+        &self,
+        request: &mut HttpRequest,
+        access_token: &str,
+    ) -> Result<(), ClientError> {
+        let proof = self.proof(request.method.as_str(), &request.url, Some(access_token))?;
+        request.headers.retain(|(name, _)| {
+            !name.eq_ignore_ascii_case("authorization") && !name.eq_ignore_ascii_case("dpop")
+        });
+        request.headers.push((String::from("Authorization"), format!("DPoP {}", access_token)));
+        request.headers.push((String::from("DPoP"), proof));
+        Ok(())
+    }
+
+    /// Sends an authorized request to a resource, retrying once with a new proof if the resource
+    /// requires a nonce.
+    ///
+    /// See [RFC 9449, section 9](https://tools.ietf.org/html/rfc9449#section-9).
+    pub fn execute<H: HttpClient>(
+        &self,
+        http_client: &H,
+        mut request: HttpRequest,
+        access_token: &str,
+    ) -> Result<HttpResponse, ClientError> {
+        self.authorize(&mut request, access_token)?;
+        let response = http_client.execute(request.clone())?;
+        if self.update_nonce(&request.url, &response) && requires_nonce(&response) {
+            self.authorize(&mut request, access_token)?;
+            return http_client.execute(request);
+        }
+        Ok(response)
+    }
+
+    /// Records the key a token response is bound to in its `cnf` member, if the token type is
+    /// DPoP.
+    pub(crate) fn confirm(&self, mut json: Value) -> Value {
+        let bound = json.get("token_type")
+            .and_then(Value::as_str)
+            .is_some_and(|token_type| token_type.eq_ignore_ascii_case("DPoP"));
+        if !bound {
+            return json;
+        }
+        if let Some(cnf) = json.as_object_mut()
+            .map(|obj| obj.entry("cnf").or_insert_with(|| Value::Object(Map::new())))
+            .and_then(Value::as_object_mut)
+        {
+            cnf.entry("jkt").or_insert_with(|| Value::from(&self.thumbprint[..]));
+        }
+        json
+    }
+}
+
+/// Returns true if a resource response is a `use_dpop_nonce` error.
+fn requires_nonce(response: &HttpResponse) -> bool {
+    response.status == 401
+        && response.header("WWW-Authenticate")
+            .is_some_and(|challenge| challenge.contains("use_dpop_nonce"))
+}
+
+impl PartialEq for DpopKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.thumbprint == other.thumbprint
+    }
+}
+
+impl Eq for DpopKey {}
+
+impl fmt::Debug for DpopKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("DpopKey")
+            .field("alg", &self.key.algorithm())
+            .field("thumbprint", &self.thumbprint)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64;
+    use openssl::sha::sha256;
+    use url::Url;
+
+    use client::http::{HttpRequest, HttpResponse, MemoryClient};
+    use jwt::{Jwks, Jwt, SigningKey};
+    use super::DpopKey;
+
+    fn resource() -> Url {
+        Url::parse("https://example.com/resource?page=2#top").unwrap()
+    }
+
+    fn verify(key: &DpopKey, proof: &str) -> Jwt {
+        let jwt = Jwt::decode(proof).unwrap();
+        jwt.verify(&Jwks { keys: vec![key.jwk().clone()] }).unwrap();
+        jwt
+    }
+
+    #[test]
+    fn proof() {
+        let key = DpopKey::generate().unwrap();
+        let jwt = verify(&key, &key.proof("POST", &resource(), None).unwrap());
+        let claims = jwt.claims();
+        assert_eq!(Some(&json!("POST")), claims.get("htm"));
+        assert_eq!(Some(&json!("https://example.com/resource")), claims.get("htu"));
+        assert!(claims.get("jti").is_some());
+        assert!(claims.get("iat").is_some());
+        assert_eq!(None, claims.get("ath"));
+        assert_eq!(None, claims.get("nonce"));
+        assert_eq!("ES256", jwt.header().alg);
+        assert_eq!(Some(String::from("dpop+jwt")), jwt.header().typ);
+    }
+
+    #[test]
+    fn proof_with_access_token_and_nonce() {
+        let key = DpopKey::generate().unwrap();
+        key.set_nonce(&Url::parse("https://example.com/other").unwrap(), "n-1");
+        let jwt = verify(&key, &key.proof("GET", &resource(), Some("aaaaaaaa")).unwrap());
+        let ath = base64::encode_config(&sha256(b"aaaaaaaa"), base64::URL_SAFE_NO_PAD);
+        assert_eq!(Some(&json!(ath)), jwt.claims().get("ath"));
+        assert_eq!(Some(&json!("n-1")), jwt.claims().get("nonce"));
+
+        let other = Url::parse("https://example.org/").unwrap();
+        let jwt = verify(&key, &key.proof("GET", &other, None).unwrap());
+        assert_eq!(None, jwt.claims().get("nonce"));
+    }
+
+    #[test]
+    fn new_rejects_hs256() {
+        assert!(DpopKey::new(SigningKey::hs256("secret")).is_err());
+    }
+
+    #[test]
+    fn execute_retries_with_nonce() {
+        let key = DpopKey::generate().unwrap();
+        let http = MemoryClient::new();
+        http.push(HttpResponse {
+            status: 401,
+            headers: vec![
+                (String::from("WWW-Authenticate"), String::from(r#"DPoP error="use_dpop_nonce""#)),
+                (String::from("DPoP-Nonce"), String::from("n-1")),
+            ],
+            body: Vec::new(),
+        });
+        http.push_json(200, "{}");
+
+        let response = key.execute(&http, HttpRequest::get(resource()), "aaaaaaaa").unwrap();
+        assert_eq!(200, response.status);
+
+        let requests = http.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!(Some("DPoP aaaaaaaa"), requests[1].header("authorization"));
+        let jwt = verify(&key, requests[1].header("dpop").unwrap());
+        assert_eq!(Some(&json!("n-1")), jwt.claims().get("nonce"));
+        assert_eq!(1, requests[1].headers.iter().filter(|(name, _)| name == "DPoP").count());
+    }
+
+    #[test]
+    fn confirm() {
+        let key = DpopKey::generate().unwrap();
+        let json = key.confirm(json!({"token_type": "DPoP", "access_token": "aaaaaaaa"}));
+        assert_eq!(json!({"jkt": key.thumbprint()}), json["cnf"]);
+        let json = key.confirm(json!({"token_type": "Bearer", "access_token": "aaaaaaaa"}));
+        assert_eq!(None, json.get("cnf"));
+    }
+}
@@ -36,11 +36,60 @@ pub enum OAuth2ErrorCode {
     /// resource owner.
     InvalidScope,
 
+    /// The authorization request is still pending as the end user hasn't yet completed the
+    /// user-interaction steps.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    AuthorizationPending,
+
+    /// The authorization request is still pending and polling should continue, but the interval
+    /// must be increased by 5 seconds for this and all subsequent requests.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    SlowDown,
+
+    /// The resource owner or authorization server denied the request.
+    AccessDenied,
+
+    /// The authorization server does not support obtaining an authorization code using this
+    /// method.
+    ///
+    /// See [RFC 6749, section 4.1.2.1](http://tools.ietf.org/html/rfc6749#section-4.1.2.1).
+    UnsupportedResponseType,
+
+    /// The authorization server encountered an unexpected condition that prevented it from
+    /// fulfilling the request.
+    ///
+    /// See [RFC 6749, section 4.1.2.1](http://tools.ietf.org/html/rfc6749#section-4.1.2.1).
+    ServerError,
+
+    /// The authorization server is currently unable to handle the request due to a temporary
+    /// overloading or maintenance of the server.
+    ///
+    /// See [RFC 6749, section 4.1.2.1](http://tools.ietf.org/html/rfc6749#section-4.1.2.1).
+    TemporarilyUnavailable,
+
+    /// The device code has expired, and the device authorization session has concluded.
+    ///
+    /// See [RFC 8628, section 3.5](https://tools.ietf.org/html/rfc8628#section-3.5).
+    ExpiredToken,
+
+    /// The DPoP proof is invalid.
+    ///
+    /// See [RFC 9449, section 5](https://tools.ietf.org/html/rfc9449#section-5).
+    InvalidDpopProof,
+
+    /// The authorization server requires a nonce in the DPoP proof, provided in the `DPoP-Nonce`
+    /// header.
+    ///
+    /// See [RFC 9449, section 8](https://tools.ietf.org/html/rfc9449#section-8).
+    UseDpopNonce,
+
     /// An unrecognized error code, not defined in RFC 6749.
     Unrecognized(String),
 }
 
-impl<'a> From<&'a str> for OAuth2ErrorCode {
+impl From<&str> for OAuth2ErrorCode {
     fn from(s: &str) -> OAuth2ErrorCode {
         match s {
             "invalid_request" => OAuth2ErrorCode::InvalidRequest,
@@ -49,6 +98,15 @@ impl<'a> From<&'a str> for OAuth2ErrorCode {
             "unauthorized_client" => OAuth2ErrorCode::UnauthorizedClient,
             "unsupported_grant_type" => OAuth2ErrorCode::UnsupportedGrantType,
             "invalid_scope" => OAuth2ErrorCode::InvalidScope,
+            "authorization_pending" => OAuth2ErrorCode::AuthorizationPending,
+            "slow_down" => OAuth2ErrorCode::SlowDown,
+            "access_denied" => OAuth2ErrorCode::AccessDenied,
+            "unsupported_response_type" => OAuth2ErrorCode::UnsupportedResponseType,
+            "server_error" => OAuth2ErrorCode::ServerError,
+            "temporarily_unavailable" => OAuth2ErrorCode::TemporarilyUnavailable,
+            "expired_token" => OAuth2ErrorCode::ExpiredToken,
+            "invalid_dpop_proof" => OAuth2ErrorCode::InvalidDpopProof,
+            "use_dpop_nonce" => OAuth2ErrorCode::UseDpopNonce,
             s => OAuth2ErrorCode::Unrecognized(s.to_owned()),
         }
     }
@@ -131,6 +189,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn from_response_device() {
+        let json = r#"{"error":"authorization_pending"}"#.parse().unwrap();
+        assert_eq!(
+            OAuth2Error {
+                code: OAuth2ErrorCode::AuthorizationPending,
+                description: None,
+                uri: None,
+            },
+            OAuth2Error::from_response(&json).unwrap()
+        );
+    }
+
     #[test]
     fn from_response_with_description() {
         let json = r#"{"error":"invalid_request","error_description":"foo"}"#
@@ -0,0 +1,754 @@
+//! JSON Web Tokens.
+//!
+//! Decoding and signature verification of JWTs using keys from a JSON Web Key Set, and signing
+//! of JWTs such as client assertions.
+//!
+//! See [RFC 7519](https://tools.ietf.org/html/rfc7519), [RFC 7515](https://tools.ietf.org/html/rfc7515)
+//! and [RFC 7517](https://tools.ietf.org/html/rfc7517).
+
+use std::error::Error;
+use std::fmt;
+
+use base64;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::error::ErrorStack;
+use openssl::hash::{MessageDigest, hash};
+use openssl::nid::Nid;
+use openssl::rand::rand_bytes;
+use openssl::pkey::{HasPublic, PKey, PKeyRef, Private, Public};
+use openssl::rsa::Rsa;
+use openssl::sign::{Signer, Verifier};
+use serde_json::{self, Map, Value};
+use url::Url;
+
+use client::ClientError;
+use client::http::{HttpClient, HttpRequest};
+use client::response::{FromResponse, ParseError};
+use secret::Secret;
+
+/// JWS signature algorithms.
+///
+/// See [RFC 7518, section 3.1](https://tools.ietf.org/html/rfc7518#section-3.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// HMAC using SHA-256.
+    ///
+    /// Only supported for signing.
+    HS256,
+
+    /// RSASSA-PKCS1-v1_5 using SHA-256.
+    RS256,
+
+    /// ECDSA using P-256 and SHA-256.
+    ES256,
+}
+
+impl Algorithm {
+    /// Returns the `alg` header parameter value.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Algorithm::HS256 => "HS256",
+            Algorithm::RS256 => "RS256",
+            Algorithm::ES256 => "ES256",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "HS256" => Some(Algorithm::HS256),
+            "RS256" => Some(Algorithm::RS256),
+            "ES256" => Some(Algorithm::ES256),
+            _ => None,
+        }
+    }
+}
+
+/// JWT errors.
+#[derive(Debug)]
+pub enum JwtError {
+    /// The token is not a well-formed JWS compact serialization.
+    Malformed,
+
+    /// The token is signed with an unsupported algorithm.
+    UnsupportedAlgorithm(String),
+
+    /// No key in the key set matches the token.
+    KeyNotFound,
+
+    /// The key is not usable for the token's algorithm.
+    InvalidKey,
+
+    /// The signature does not verify.
+    InvalidSignature,
+
+    /// The claim failed validation.
+    InvalidClaim(&'static str),
+
+    /// OpenSSL error.
+    OpenSsl(ErrorStack),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            JwtError::Malformed => write!(f, "Malformed JWT"),
+            JwtError::UnsupportedAlgorithm(ref alg) => write!(f, "Unsupported algorithm {}", alg),
+            JwtError::KeyNotFound => write!(f, "No matching key found"),
+            JwtError::InvalidKey => write!(f, "Invalid key"),
+            JwtError::InvalidSignature => write!(f, "Invalid signature"),
+            JwtError::InvalidClaim(claim) => write!(f, "Invalid claim {}", claim),
+            JwtError::OpenSsl(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for JwtError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            JwtError::OpenSsl(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorStack> for JwtError {
+    fn from(err: ErrorStack) -> Self {
+        JwtError::OpenSsl(err)
+    }
+}
+
+/// JOSE header.
+///
+/// See [RFC 7515, section 4](https://tools.ietf.org/html/rfc7515#section-4).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// Signature algorithm.
+    pub alg: String,
+
+    /// Key ID.
+    pub kid: Option<String>,
+
+    /// Media type of the token.
+    pub typ: Option<String>,
+}
+
+/// A decoded, unverified JWT.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jwt {
+    header: Header,
+    claims: Map<String, Value>,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+fn decode_part(part: &str) -> Result<Vec<u8>, JwtError> {
+    base64::decode_config(part, base64::URL_SAFE_NO_PAD).map_err(|_| JwtError::Malformed)
+}
+
+fn decode_object(part: &str) -> Result<Map<String, Value>, JwtError> {
+    match serde_json::from_slice(&decode_part(part)?) {
+        Ok(Value::Object(obj)) => Ok(obj),
+        _ => Err(JwtError::Malformed),
+    }
+}
+
+impl Jwt {
+    /// Decodes a JWT in JWS compact serialization without verifying it.
+    pub fn decode(token: &str) -> Result<Self, JwtError> {
+        let mut parts = token.split('.');
+        let (header, claims, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(header), Some(claims), Some(signature)) => (header, claims, signature),
+            _ => return Err(JwtError::Malformed),
+        };
+        if parts.next().is_some() {
+            return Err(JwtError::Malformed);
+        }
+
+        let header_obj = decode_object(header)?;
+        let alg = header_obj.get("alg").and_then(Value::as_str).ok_or(JwtError::Malformed)?;
+        let string = |key| header_obj.get(key).and_then(Value::as_str).map(String::from);
+
+        Ok(Jwt {
+            header: Header {
+                alg: alg.into(),
+                kid: string("kid"),
+                typ: string("typ"),
+            },
+            claims: decode_object(claims)?,
+            signing_input: format!("{}.{}", header, claims),
+            signature: decode_part(signature)?,
+        })
+    }
+
+    /// Returns the JOSE header.
+    pub fn header(&self) -> &Header { &self.header }
+
+    /// Returns the unverified claims.
+    pub fn claims(&self) -> &Map<String, Value> { &self.claims }
+
+    /// Verifies the signature using a key from the key set.
+    ///
+    /// The key is selected by the `kid` header parameter if present, otherwise the first key
+    /// usable with the algorithm is used.
+    pub fn verify(&self, jwks: &Jwks) -> Result<(), JwtError> {
+        let alg = match Algorithm::from_name(&self.header.alg) {
+            // Symmetric keys are never published in a key set.
+            Some(Algorithm::HS256) | None => {
+                return Err(JwtError::UnsupportedAlgorithm(self.header.alg.clone()));
+            },
+            Some(alg) => alg,
+        };
+        let jwk = jwks.find(self.header.kid.as_ref().map(|s| &s[..]), alg)
+            .ok_or(JwtError::KeyNotFound)?;
+        let key = jwk.public_key()?;
+
+        let valid = match alg {
+            Algorithm::HS256 => unreachable!(),
+            Algorithm::RS256 => {
+                let mut verifier = Verifier::new(MessageDigest::sha256(), &key)?;
+                verifier.update(self.signing_input.as_bytes())?;
+                verifier.verify(&self.signature)?
+            },
+            Algorithm::ES256 => {
+                if self.signature.len() != 64 {
+                    return Err(JwtError::InvalidSignature);
+                }
+                let r = BigNum::from_slice(&self.signature[..32])?;
+                let s = BigNum::from_slice(&self.signature[32..])?;
+                let der = EcdsaSig::from_private_components(r, s)?.to_der()?;
+                let mut verifier = Verifier::new(MessageDigest::sha256(), &key)?;
+                verifier.update(self.signing_input.as_bytes())?;
+                verifier.verify(&der)?
+            },
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(JwtError::InvalidSignature)
+        }
+    }
+}
+
+fn encode_part(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Generates a random `jti` claim value from 16 octets of entropy.
+pub(crate) fn random_jti() -> Result<String, ErrorStack> {
+    let mut jti = [0; 16];
+    rand_bytes(&mut jti)?;
+    Ok(encode_part(&jti))
+}
+
+#[derive(Clone)]
+enum KeyMaterial {
+    Hmac(Secret),
+    Private(PKey<Private>),
+}
+
+/// A key for signing JWTs.
+///
+/// `Debug` shows only the algorithm and key ID.
+#[derive(Clone)]
+pub struct SigningKey {
+    alg: Algorithm,
+    key: KeyMaterial,
+    kid: Option<String>,
+}
+
+impl SigningKey {
+    /// Creates an HS256 key from a shared secret, such as a client secret.
+    pub fn hs256(secret: &str) -> Self {
+        SigningKey {
+            alg: Algorithm::HS256,
+            key: KeyMaterial::Hmac(Secret::from(secret)),
+            kid: None,
+        }
+    }
+
+    /// Creates an RS256 or ES256 key from an RSA or P-256 EC private key.
+    pub fn from_private_key(key: PKey<Private>) -> Result<Self, JwtError> {
+        let alg = if key.rsa().is_ok() {
+            Algorithm::RS256
+        } else {
+            match key.ec_key() {
+                Ok(ref ec) if ec.group().curve_name() == Some(Nid::X9_62_PRIME256V1) => {
+                    Algorithm::ES256
+                },
+                _ => return Err(JwtError::InvalidKey),
+            }
+        };
+        Ok(SigningKey { alg, key: KeyMaterial::Private(key), kid: None })
+    }
+
+    /// Creates an RS256 or ES256 key from a PEM-encoded private key.
+    pub fn from_pem(pem: &[u8]) -> Result<Self, JwtError> {
+        SigningKey::from_private_key(PKey::private_key_from_pem(pem)?)
+    }
+
+    /// Sets the key ID, sent in the `kid` header parameter.
+    pub fn kid(mut self, kid: String) -> Self {
+        self.kid = Some(kid);
+        self
+    }
+
+    /// Returns the signature algorithm.
+    pub fn algorithm(&self) -> Algorithm { self.alg }
+
+    /// Returns the key ID.
+    pub fn key_id(&self) -> Option<&str> { self.kid.as_ref().map(|s| &s[..]) }
+
+    /// Returns the public JWK of an RS256 or ES256 key, with its key ID.
+    pub fn public_jwk(&self) -> Result<Jwk, JwtError> {
+        match self.key {
+            KeyMaterial::Private(ref key) => {
+                let mut jwk = Jwk::from_public_key(key)?;
+                jwk.kid = self.kid.clone();
+                jwk.alg = Some(self.alg.as_str().into());
+                Ok(jwk)
+            },
+            KeyMaterial::Hmac(_) => Err(JwtError::InvalidKey),
+        }
+    }
+
+    /// Signs claims, returning a JWT in JWS compact serialization.
+    pub fn sign(&self, claims: &Value) -> Result<String, JwtError> {
+        let mut header = Map::new();
+        header.insert(String::from("typ"), Value::from("JWT"));
+        if let Some(ref kid) = self.kid {
+            header.insert(String::from("kid"), Value::from(&kid[..]));
+        }
+        self.sign_with_header(header, claims)
+    }
+
+    /// Signs claims with additional header parameters. The `alg` parameter is set by the key.
+    pub(crate) fn sign_with_header(
+        &self,
+        mut header: Map<String, Value>,
+        claims: &Value,
+    ) -> Result<String, JwtError> {
+        header.insert(String::from("alg"), Value::from(self.alg.as_str()));
+        let input = format!(
+            "{}.{}",
+            encode_part(Value::Object(header).to_string().as_bytes()),
+            encode_part(claims.to_string().as_bytes()),
+        );
+
+        let signature = match self.key {
+            KeyMaterial::Hmac(ref secret) => {
+                let key = PKey::hmac(secret.secret().as_bytes())?;
+                let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+                signer.update(input.as_bytes())?;
+                signer.sign_to_vec()?
+            },
+            KeyMaterial::Private(ref key) => {
+                let mut signer = Signer::new(MessageDigest::sha256(), key)?;
+                signer.update(input.as_bytes())?;
+                let signature = signer.sign_to_vec()?;
+                if self.alg == Algorithm::ES256 {
+                    let sig = EcdsaSig::from_der(&signature)?;
+                    let mut signature = sig.r().to_vec_padded(32)?;
+                    signature.extend(sig.s().to_vec_padded(32)?);
+                    signature
+                } else {
+                    signature
+                }
+            },
+        };
+
+        Ok(format!("{}.{}", input, encode_part(&signature)))
+    }
+}
+
+impl PartialEq for SigningKey {
+    fn eq(&self, other: &Self) -> bool {
+        let key_eq = match (&self.key, &other.key) {
+            (KeyMaterial::Hmac(a), KeyMaterial::Hmac(b)) => a == b,
+            (KeyMaterial::Private(a), KeyMaterial::Private(b)) => a.public_eq(b),
+            _ => false,
+        };
+        self.alg == other.alg && self.kid == other.kid && key_eq
+    }
+}
+
+impl Eq for SigningKey {}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("SigningKey")
+            .field("alg", &self.alg)
+            .field("kid", &self.kid)
+            .finish()
+    }
+}
+
+/// JSON Web Key.
+///
+/// Only public RSA and P-256 EC keys are supported.
+///
+/// See [RFC 7517, section 4](https://tools.ietf.org/html/rfc7517#section-4).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    /// Key type.
+    pub kty: String,
+
+    /// Key ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+
+    /// Algorithm intended for use with the key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+
+    /// Intended use of the key.
+    #[serde(default, rename = "use", skip_serializing_if = "Option::is_none")]
+    pub use_: Option<String>,
+
+    /// RSA modulus.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+
+    /// RSA exponent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+
+    /// EC curve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+
+    /// EC x coordinate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+
+    /// EC y coordinate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+}
+
+fn decode_bignum(value: &Option<String>) -> Result<BigNum, JwtError> {
+    let value = value.as_ref().ok_or(JwtError::InvalidKey)?;
+    let bytes = decode_part(value).map_err(|_| JwtError::InvalidKey)?;
+    Ok(BigNum::from_slice(&bytes)?)
+}
+
+impl Jwk {
+    /// Creates a JWK from an RSA or P-256 EC public key.
+    pub fn from_public_key<T: HasPublic>(key: &PKeyRef<T>) -> Result<Self, JwtError> {
+        let mut jwk = Jwk {
+            kty: String::new(),
+            kid: None,
+            alg: None,
+            use_: Some(String::from("sig")),
+            n: None,
+            e: None,
+            crv: None,
+            x: None,
+            y: None,
+        };
+        if let Ok(rsa) = key.rsa() {
+            jwk.kty = String::from("RSA");
+            jwk.n = Some(encode_part(&rsa.n().to_vec()));
+            jwk.e = Some(encode_part(&rsa.e().to_vec()));
+            return Ok(jwk);
+        }
+        let ec = key.ec_key().map_err(|_| JwtError::InvalidKey)?;
+        if ec.group().curve_name() != Some(Nid::X9_62_PRIME256V1) {
+            return Err(JwtError::InvalidKey);
+        }
+        let mut ctx = BigNumContext::new()?;
+        let mut x = BigNum::new()?;
+        let mut y = BigNum::new()?;
+        ec.public_key().affine_coordinates(ec.group(), &mut x, &mut y, &mut ctx)?;
+        jwk.kty = String::from("EC");
+        jwk.crv = Some(String::from("P-256"));
+        jwk.x = Some(encode_part(&x.to_vec_padded(32)?));
+        jwk.y = Some(encode_part(&y.to_vec_padded(32)?));
+        Ok(jwk)
+    }
+
+    /// Returns the JWK thumbprint, the base64url-encoded SHA-256 digest of the required members
+    /// of the key.
+    ///
+    /// See [RFC 7638, section 3](https://tools.ietf.org/html/rfc7638#section-3).
+    pub fn thumbprint(&self) -> Result<String, JwtError> {
+        fn member(value: &Option<String>) -> Result<&str, JwtError> {
+            value.as_ref().map(|s| &s[..]).ok_or(JwtError::InvalidKey)
+        }
+        // Members in lexicographic order, without whitespace.
+        let json = match &self.kty[..] {
+            "RSA" => format!(
+                r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+                member(&self.e)?,
+                member(&self.n)?,
+            ),
+            "EC" => format!(
+                r#"{{"crv":"{}","kty":"EC","x":"{}","y":"{}"}}"#,
+                member(&self.crv)?,
+                member(&self.x)?,
+                member(&self.y)?,
+            ),
+            _ => return Err(JwtError::InvalidKey),
+        };
+        Ok(encode_part(&hash(MessageDigest::sha256(), json.as_bytes())?))
+    }
+
+    fn usable_with(&self, alg: Algorithm) -> bool {
+        let kty = match alg {
+            Algorithm::HS256 => "oct",
+            Algorithm::RS256 => "RSA",
+            Algorithm::ES256 => "EC",
+        };
+        self.kty == kty
+            && self.use_.as_ref().is_none_or(|u| u == "sig")
+            && self.alg.as_ref().is_none_or(|a| a == alg.as_str())
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> Result<PKey<Public>, JwtError> {
+        match &self.kty[..] {
+            "RSA" => {
+                let rsa = Rsa::from_public_components(
+                    decode_bignum(&self.n)?,
+                    decode_bignum(&self.e)?,
+                )?;
+                Ok(PKey::from_rsa(rsa)?)
+            },
+            "EC" if self.crv.as_ref().map(|s| &s[..]) == Some("P-256") => {
+                let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+                let x = decode_bignum(&self.x)?;
+                let y = decode_bignum(&self.y)?;
+                let ec = EcKey::from_public_key_affine_coordinates(&group, &x, &y)?;
+                Ok(PKey::from_ec_key(ec)?)
+            },
+            _ => Err(JwtError::InvalidKey),
+        }
+    }
+}
+
+/// JSON Web Key Set.
+///
+/// See [RFC 7517, section 5](https://tools.ietf.org/html/rfc7517#section-5).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwks {
+    /// Keys.
+    pub keys: Vec<Jwk>,
+}
+
+impl Jwks {
+    /// Fetches a key set.
+    pub fn fetch<H: HttpClient>(http_client: &H, uri: &Url) -> Result<Self, ClientError> {
+        let response = http_client.execute(HttpRequest::get(uri.clone()))?;
+        if !response.is_success() {
+            return Err(ClientError::Status(response.status));
+        }
+        let json = serde_json::from_slice(&response.body)?;
+        let jwks = Jwks::from_response(&json)?;
+        Ok(jwks)
+    }
+
+    fn find(&self, kid: Option<&str>, alg: Algorithm) -> Option<&Jwk> {
+        self.keys.iter()
+            .filter(|jwk| jwk.usable_with(alg))
+            .find(|jwk| kid.is_none() || jwk.kid.as_ref().map(|s| &s[..]) == kid)
+    }
+}
+
+impl FromResponse for Jwks {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let keys = json.as_object()
+            .ok_or(ParseError::ExpectedType("object"))?
+            .get("keys")
+            .and_then(Value::as_array)
+            .ok_or(ParseError::ExpectedFieldType("keys", "array"))?;
+
+        // Malformed keys and keys of unsupported types are ignored.
+        let keys = keys.iter()
+            .filter_map(|key| serde_json::from_value::<Jwk>(key.clone()).ok())
+            .filter(|jwk| jwk.kty == "RSA" || jwk.kty == "EC")
+            .collect();
+        Ok(Jwks { keys })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use base64;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::rsa::Rsa;
+    use openssl::sign::Signer;
+    use serde_json::{self, Value};
+
+    use client::response::{FromResponse, ParseError};
+    use super::{Algorithm, Jwk, Jwks, Jwt, JwtError, SigningKey};
+
+    fn encode(bytes: &[u8]) -> String {
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Signs claims with a test key.
+    pub fn sign(key: &PKey<Private>, kid: &str, claims: &Value) -> String {
+        SigningKey::from_private_key(key.clone()).unwrap()
+            .kid(kid.into())
+            .sign(claims)
+            .unwrap()
+    }
+
+    /// Returns the public JWK of a test key.
+    pub fn jwk(key: &PKey<Private>, kid: &str) -> Jwk {
+        let mut jwk = Jwk::from_public_key(key).unwrap();
+        jwk.kid = Some(kid.into());
+        jwk
+    }
+
+    pub fn rsa_key() -> PKey<Private> {
+        PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap()
+    }
+
+    pub fn ec_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn decode() {
+        let token = sign(&ec_key(), "1", &json!({ "sub": "foo" }));
+        let jwt = Jwt::decode(&token).unwrap();
+        assert_eq!("ES256", jwt.header().alg);
+        assert_eq!(Some(String::from("1")), jwt.header().kid);
+        assert_eq!(Some(&json!("foo")), jwt.claims().get("sub"));
+    }
+
+    #[test]
+    fn decode_malformed() {
+        match Jwt::decode("aaaa.bbbb") {
+            Err(JwtError::Malformed) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn verify_rs256() {
+        let key = rsa_key();
+        let jwks = Jwks { keys: vec![jwk(&ec_key(), "1"), jwk(&key, "2")] };
+        let token = sign(&key, "2", &json!({ "sub": "foo" }));
+        Jwt::decode(&token).unwrap().verify(&jwks).unwrap();
+    }
+
+    #[test]
+    fn verify_es256() {
+        let key = ec_key();
+        let jwks = Jwks { keys: vec![jwk(&key, "1")] };
+        let token = sign(&key, "1", &json!({ "sub": "foo" }));
+        Jwt::decode(&token).unwrap().verify(&jwks).unwrap();
+    }
+
+    #[test]
+    fn verify_wrong_key() {
+        let jwks = Jwks { keys: vec![jwk(&ec_key(), "1")] };
+        let token = sign(&ec_key(), "1", &json!({ "sub": "foo" }));
+        match Jwt::decode(&token).unwrap().verify(&jwks) {
+            Err(JwtError::InvalidSignature) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn verify_unknown_kid() {
+        let key = ec_key();
+        let jwks = Jwks { keys: vec![jwk(&key, "1")] };
+        let token = sign(&key, "2", &json!({ "sub": "foo" }));
+        match Jwt::decode(&token).unwrap().verify(&jwks) {
+            Err(JwtError::KeyNotFound) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn jwks_from_response() {
+        let json = r#"
+            {
+                "keys":[
+                    {"kty":"RSA","kid":"a","n":"AQAB","e":"AQAB","alg":"RS256","use":"sig"},
+                    {"kty":"oct","k":"c2VjcmV0"},
+                    {"kid":"b"}
+                ]
+            }
+        "#.parse().unwrap();
+        let jwks = Jwks::from_response(&json).unwrap();
+        assert_eq!(1, jwks.keys.len());
+        assert_eq!(Some(String::from("a")), jwks.keys[0].kid);
+        assert_eq!(Some(String::from("sig")), jwks.keys[0].use_);
+    }
+
+    #[test]
+    fn jwks_from_response_without_keys() {
+        let json = "{}".parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("keys", "array"),
+            Jwks::from_response(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn sign_hs256() {
+        let token = SigningKey::hs256("secret").sign(&json!({ "sub": "foo" })).unwrap();
+        let jwt = Jwt::decode(&token).unwrap();
+        assert_eq!("HS256", jwt.header().alg);
+        assert_eq!(None, jwt.header().kid);
+
+        let key = PKey::hmac(b"secret").unwrap();
+        let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+        signer.update(token.rsplit_once('.').unwrap().0.as_bytes()).unwrap();
+        assert_eq!(encode(&signer.sign_to_vec().unwrap()), token.rsplit('.').next().unwrap());
+    }
+
+    #[test]
+    fn verify_hs256_unsupported() {
+        let token = SigningKey::hs256("secret").sign(&json!({ "sub": "foo" })).unwrap();
+        let jwks = Jwks { keys: vec![jwk(&ec_key(), "1")] };
+        match Jwt::decode(&token).unwrap().verify(&jwks) {
+            Err(JwtError::UnsupportedAlgorithm(ref alg)) if alg == "HS256" => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn signing_key_from_pem() {
+        let pem = rsa_key().private_key_to_pem_pkcs8().unwrap();
+        let key = SigningKey::from_pem(&pem).unwrap().kid(String::from("1"));
+        assert_eq!(Algorithm::RS256, key.algorithm());
+        assert_eq!(Some("1"), key.key_id());
+        assert!(!format!("{:?}", key).contains("PRIVATE"));
+    }
+
+    #[test]
+    fn public_jwk() {
+        let key = ec_key();
+        let jwk = SigningKey::from_private_key(key.clone()).unwrap()
+            .kid(String::from("1"))
+            .public_jwk()
+            .unwrap();
+        assert_eq!(Some(String::from("ES256")), jwk.alg);
+        assert!(key.public_eq(&jwk.public_key().unwrap()));
+        assert!(SigningKey::hs256("secret").public_jwk().is_err());
+    }
+
+    #[test]
+    fn jwk_thumbprint() {
+        // RFC 7638, section 3.1.
+        let jwk: Jwk = serde_json::from_value(json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29",
+        })).unwrap();
+        assert_eq!("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs", jwk.thumbprint().unwrap());
+    }
+}
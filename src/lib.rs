@@ -16,15 +16,18 @@
 //! - Google
 //!   - Web
 //!   - Installed
+//!   - Service accounts
 //! - GitHub
 //! - Imgur
 //!
-//! Support for other providers can be added by implementing the `Provider` trait.
+//! Other providers can be configured from their authorization server metadata using
+//! `provider::discovery::DiscoveredProvider`, or supported by implementing the `Provider` trait.
 //!
 //! ## Token types
 //!
-//! The only supported token type is Bearer. Support for others can be added by implementing the
-//! `Token` trait.
+//! Bearer tokens are supported, optionally with an OpenID Connect ID token, as are DPoP tokens
+//! bound to a `dpop::DpopKey`. Support for others can be added by implementing the `Token`
+//! trait.
 //!
 //! ## Examples
 //!
@@ -52,6 +55,20 @@
 //! println!("Authorize the application by clicking on the link: {}", auth_uri);
 //! ```
 //!
+//! ### Using PKCE
+//!
+//! ```
+//! # use inth_oauth2::Client;
+//! # use inth_oauth2::provider::google::Installed;
+//! # let client = Client::new(Installed, String::new(), String::new(), None);
+//! use inth_oauth2::pkce::{ChallengeMethod, CodeVerifier};
+//!
+//! let verifier = CodeVerifier::new();
+//! let challenge = verifier.challenge(ChallengeMethod::S256);
+//! let auth_uri = client.auth_uri_with_challenge(Some("scope"), Some("state"), &challenge);
+//! // Later, pass the verifier to `request_token_with_verifier`.
+//! ```
+//!
 //! ### Requesting an access token
 //!
 //! ```no_run
@@ -59,15 +76,17 @@
 //! # extern crate reqwest;
 //! use std::io;
 //! use inth_oauth2::{Client, Token};
+//! # use inth_oauth2::pkce::CodeVerifier;
 //! # use inth_oauth2::provider::google::Installed;
 //! # fn main() {
 //! # let client = Client::new(Installed, String::new(), String::new(), None);
+//! # let verifier = CodeVerifier::new();
 //!
 //! let mut code = String::new();
 //! io::stdin().read_line(&mut code).unwrap();
 //!
 //! let http = reqwest::Client::new();
-//! let token = client.request_token(&http, code.trim()).unwrap();
+//! let token = client.request_token_with_verifier(&http, code.trim(), &verifier).unwrap();
 //! println!("{}", token.access_token());
 //! # }
 //! ```
@@ -82,7 +101,7 @@
 //! # fn main() {
 //! # let client = Client::new(Installed, String::new(), String::new(), None);
 //! # let http = reqwest::Client::new();
-//! # let token = client.request_token(&http, "").unwrap();
+//! # let token = client.request_token_with_verifier(&http, "", &inth_oauth2::pkce::CodeVerifier::new()).unwrap();
 //! let token = client.refresh_token(&http, token, None).unwrap();
 //! # }
 //! ```
@@ -97,7 +116,7 @@
 //! # fn main() {
 //! # let client = Client::new(Installed, String::new(), String::new(), None);
 //! # let http = reqwest::Client::new();
-//! # let mut token = client.request_token(&http, "").unwrap();
+//! # let mut token = client.request_token_with_verifier(&http, "", &inth_oauth2::pkce::CodeVerifier::new()).unwrap();
 //! // Refresh token only if it has expired.
 //! token = client.ensure_token(&http, token).unwrap();
 //! # }
@@ -115,16 +134,23 @@
 //! # fn main() {
 //! # let oauth_client = Client::new(Installed, String::new(), String::new(), None);
 //! # let http = reqwest::Client::new();
-//! # let token = oauth_client.request_token(&http, "").unwrap();
+//! # let token = oauth_client.request_token_with_verifier(&http, "", &inth_oauth2::pkce::CodeVerifier::new()).unwrap();
 //! let request = http.get("https://example.com/resource")
 //!     .bearer_auth(token.access_token())
 //!     .build();
 //! # }
 //! ```
 //!
+//! ### Using another HTTP client
+//!
+//! Requests are sent through the `client::http::HttpClient` trait, which is implemented for
+//! `reqwest::Client` by the default `reqwest-client` feature. Implement it to use another HTTP
+//! stack, or use `client::http::MemoryClient` in tests.
+//!
 //! ### Persisting tokens
 //!
-//! All token types implement `Serialize` and `Deserialize` from `serde`.
+//! All token types implement `Serialize` and `Deserialize` from `serde`. The `store` module
+//! provides token storage which refreshed tokens can be saved to automatically.
 //!
 //! ```no_run
 //! # extern crate inth_oauth2;
@@ -135,10 +161,27 @@
 //! # fn main() {
 //! # let http = reqwest::Client::new();
 //! # let client = Client::new(Installed, String::new(), String::new(), None);
-//! # let token = client.request_token(&http, "").unwrap();
+//! # let token = client.request_token_with_verifier(&http, "", &inth_oauth2::pkce::CodeVerifier::new()).unwrap();
 //! let json = serde_json::to_string(&token).unwrap();
 //! # }
 //! ```
+//!
+//! ```no_run
+//! # extern crate inth_oauth2;
+//! # extern crate reqwest;
+//! # use inth_oauth2::Client;
+//! # use inth_oauth2::provider::google::Installed;
+//! use inth_oauth2::store::{FileStore, TokenStore};
+//!
+//! # fn main() {
+//! # let http = reqwest::Client::new();
+//! # let client = Client::new(Installed, String::new(), String::new(), None);
+//! let store = FileStore::new("tokens");
+//! let key = client.store_key("user@example.com");
+//! let token = store.load(&key).unwrap().expect("not authorized");
+//! let token = client.ensure_token_stored(&http, &store, &key, token).unwrap();
+//! # }
+//! ```
 
 #![warn(
     missing_docs,
@@ -158,15 +201,35 @@ extern crate lazy_static;
 #[macro_use]
 extern crate serde_derive;
 
+#[cfg_attr(test, macro_use)]
+extern crate serde_json;
+
+extern crate base64;
 extern crate chrono;
+#[cfg(feature = "reqwest-client")]
+extern crate futures;
+extern crate openssl;
+#[cfg(feature = "reqwest-client")]
 extern crate reqwest;
-extern crate serde_json;
+extern crate serde;
+#[cfg(all(test, feature = "reqwest-client"))]
+extern crate tokio;
+#[cfg(feature = "reqwest-client")]
+extern crate tokio_timer;
 extern crate url;
 
 pub mod token;
 pub mod provider;
 pub mod error;
 pub mod client;
+pub mod dpop;
+pub mod jwt;
+pub mod pkce;
+pub mod scope;
+pub mod secret;
+pub mod store;
 
-pub use token::{Token, Lifetime};
-pub use client::{Client, ClientError};
+pub use token::{Token, Lifetime, TokenTypeHint};
+#[cfg(feature = "reqwest-client")]
+pub use client::AsyncClient;
+pub use client::{Client, ClientAuth, ClientError};
@@ -0,0 +1,112 @@
+//! Proof Key for Code Exchange.
+//!
+//! See [RFC 7636](https://tools.ietf.org/html/rfc7636).
+
+use base64;
+use openssl::rand::rand_bytes;
+use openssl::sha::sha256;
+
+/// PKCE code challenge methods.
+///
+/// See [RFC 7636, section 4.2](https://tools.ietf.org/html/rfc7636#section-4.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeMethod {
+    /// The challenge is the verifier itself.
+    Plain,
+
+    /// The challenge is the base64url-encoded SHA-256 hash of the verifier.
+    S256,
+}
+
+impl ChallengeMethod {
+    /// Returns the `code_challenge_method` parameter value.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ChallengeMethod::Plain => "plain",
+            ChallengeMethod::S256 => "S256",
+        }
+    }
+}
+
+/// PKCE code verifier.
+///
+/// The verifier is kept by the client between the authorization request and the token request.
+///
+/// See [RFC 7636, section 4.1](https://tools.ietf.org/html/rfc7636#section-4.1).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CodeVerifier(String);
+
+impl CodeVerifier {
+    /// Generates a random code verifier from 32 octets of entropy.
+    pub fn new() -> Self {
+        let mut bytes = [0; 32];
+        rand_bytes(&mut bytes).expect("random bytes");
+        CodeVerifier(base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD))
+    }
+
+    /// Returns the code verifier.
+    pub fn secret(&self) -> &str { &self.0 }
+
+    /// Derives the code challenge for the authorization request.
+    ///
+    /// See [RFC 7636, section 4.2](https://tools.ietf.org/html/rfc7636#section-4.2).
+    pub fn challenge(&self, method: ChallengeMethod) -> CodeChallenge {
+        let challenge = match method {
+            ChallengeMethod::Plain => self.0.clone(),
+            ChallengeMethod::S256 => {
+                base64::encode_config(&sha256(self.0.as_bytes()), base64::URL_SAFE_NO_PAD)
+            },
+        };
+        CodeChallenge { challenge, method }
+    }
+}
+
+impl Default for CodeVerifier {
+    fn default() -> Self { CodeVerifier::new() }
+}
+
+/// PKCE code challenge.
+///
+/// See [RFC 7636, section 4.3](https://tools.ietf.org/html/rfc7636#section-4.3).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeChallenge {
+    challenge: String,
+    method: ChallengeMethod,
+}
+
+impl CodeChallenge {
+    /// Returns the `code_challenge` parameter value.
+    pub fn challenge(&self) -> &str { &self.challenge }
+
+    /// Returns the challenge method.
+    pub fn method(&self) -> ChallengeMethod { self.method }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChallengeMethod, CodeVerifier};
+
+    #[test]
+    fn new() {
+        let verifier = CodeVerifier::new();
+        assert_eq!(43, verifier.secret().len());
+        assert_ne!(verifier, CodeVerifier::new());
+    }
+
+    #[test]
+    fn challenge_plain() {
+        let verifier = CodeVerifier(String::from("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"));
+        let challenge = verifier.challenge(ChallengeMethod::Plain);
+        assert_eq!("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk", challenge.challenge());
+        assert_eq!("plain", challenge.method().as_str());
+    }
+
+    #[test]
+    fn challenge_s256() {
+        let verifier = CodeVerifier(String::from("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"));
+        let challenge = verifier.challenge(ChallengeMethod::S256);
+        assert_eq!("E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM", challenge.challenge());
+        assert_eq!("S256", challenge.method().as_str());
+    }
+}
@@ -0,0 +1,430 @@
+//! Authorization server metadata discovery.
+//!
+//! See [RFC 8414](https://tools.ietf.org/html/rfc8414) and [OpenID Connect Discovery
+//! 1.0](https://openid.net/specs/openid-connect-discovery-1_0.html).
+
+use std::marker::PhantomData;
+
+use serde_json::{self, Map, Value};
+use url::Url;
+
+use client::{ClientAuth, ClientError};
+use client::http::{HttpClient, HttpRequest};
+use client::response::{FromResponse, ParseError};
+use token::{Bearer, Lifetime};
+use super::Provider;
+
+/// Authorization server metadata.
+///
+/// See [RFC 8414, section 2](https://tools.ietf.org/html/rfc8414#section-2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// Issuer identifier.
+    pub issuer: Url,
+
+    /// Authorization endpoint URI.
+    pub authorization_endpoint: Option<Url>,
+
+    /// Token endpoint URI.
+    pub token_endpoint: Option<Url>,
+
+    /// JSON Web Key Set document URI.
+    pub jwks_uri: Option<Url>,
+
+    /// Token revocation endpoint URI.
+    pub revocation_endpoint: Option<Url>,
+
+    /// Token introspection endpoint URI.
+    pub introspection_endpoint: Option<Url>,
+
+    /// Device authorization endpoint URI.
+    pub device_authorization_endpoint: Option<Url>,
+
+    /// Pushed authorization request endpoint URI.
+    pub pushed_authorization_request_endpoint: Option<Url>,
+
+    /// Whether the provider only accepts pushed authorization requests.
+    pub require_pushed_authorization_requests: bool,
+
+    /// Supported scope values.
+    pub scopes_supported: Vec<String>,
+
+    /// Supported `response_type` values.
+    pub response_types_supported: Vec<String>,
+
+    /// Supported grant types.
+    pub grant_types_supported: Vec<String>,
+
+    /// Supported client authentication methods at the token endpoint.
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+
+    /// Supported PKCE code challenge methods.
+    pub code_challenge_methods_supported: Vec<String>,
+}
+
+fn url(obj: &Map<String, Value>, key: &'static str) -> Result<Option<Url>, ParseError> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_str()
+            .and_then(|s| Url::parse(s).ok())
+            .map(Some)
+            .ok_or(ParseError::ExpectedFieldType(key, "URL")),
+    }
+}
+
+fn strings(
+    obj: &Map<String, Value>,
+    key: &'static str,
+    default: &[&str],
+) -> Result<Vec<String>, ParseError> {
+    match obj.get(key) {
+        None => Ok(default.iter().map(|&s| s.into()).collect()),
+        Some(value) => value.as_array()
+            .and_then(|values| {
+                values.iter().map(|value| value.as_str().map(Into::into)).collect()
+            })
+            .ok_or(ParseError::ExpectedFieldType(key, "array of strings")),
+    }
+}
+
+impl FromResponse for Metadata {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+
+        let issuer = url(obj, "issuer")?
+            .ok_or(ParseError::ExpectedFieldType("issuer", "URL"))?;
+        let require_pushed_authorization_requests =
+            match obj.get("require_pushed_authorization_requests") {
+                None => false,
+                Some(value) => value.as_bool().ok_or(ParseError::ExpectedFieldType(
+                    "require_pushed_authorization_requests",
+                    "bool",
+                ))?,
+            };
+
+        Ok(Metadata {
+            issuer,
+            authorization_endpoint: url(obj, "authorization_endpoint")?,
+            token_endpoint: url(obj, "token_endpoint")?,
+            jwks_uri: url(obj, "jwks_uri")?,
+            revocation_endpoint: url(obj, "revocation_endpoint")?,
+            introspection_endpoint: url(obj, "introspection_endpoint")?,
+            device_authorization_endpoint: url(obj, "device_authorization_endpoint")?,
+            pushed_authorization_request_endpoint: url(
+                obj,
+                "pushed_authorization_request_endpoint",
+            )?,
+            require_pushed_authorization_requests,
+            scopes_supported: strings(obj, "scopes_supported", &[])?,
+            response_types_supported: strings(obj, "response_types_supported", &[])?,
+            grant_types_supported: strings(
+                obj,
+                "grant_types_supported",
+                &["authorization_code", "implicit"],
+            )?,
+            token_endpoint_auth_methods_supported: strings(
+                obj,
+                "token_endpoint_auth_methods_supported",
+                &["client_secret_basic"],
+            )?,
+            code_challenge_methods_supported: strings(
+                obj,
+                "code_challenge_methods_supported",
+                &[],
+            )?,
+        })
+    }
+}
+
+/// Returns the RFC 8414 and OpenID Connect metadata URIs for an issuer, in that order.
+fn well_known_uris(issuer: &Url) -> [Url; 2] {
+    let path = issuer.path().trim_end_matches('/');
+
+    let mut oauth = issuer.clone();
+    oauth.set_path(&format!("/.well-known/oauth-authorization-server{}", path));
+    oauth.set_query(None);
+
+    let mut openid = issuer.clone();
+    openid.set_path(&format!("{}/.well-known/openid-configuration", path));
+    openid.set_query(None);
+
+    [oauth, openid]
+}
+
+/// Provider configured from authorization server metadata.
+///
+/// Issues bearer tokens with lifetime `L`, which must be chosen to match the provider.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate inth_oauth2;
+/// # extern crate reqwest;
+/// use inth_oauth2::Client;
+/// use inth_oauth2::provider::discovery::DiscoveredProvider;
+/// use inth_oauth2::token::Refresh;
+///
+/// # fn main() {
+/// let http = reqwest::Client::new();
+/// let provider = DiscoveredProvider::<Refresh>::discover(&http, "https://accounts.google.com")
+///     .unwrap();
+/// let client = Client::new(provider, String::new(), String::new(), None);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredProvider<L> {
+    metadata: Metadata,
+    auth_uri: Url,
+    token_uri: Url,
+    lifetime: PhantomData<L>,
+}
+
+impl<L: Lifetime> DiscoveredProvider<L> {
+    /// Creates a provider from authorization server metadata.
+    ///
+    /// The metadata must include the authorization and token endpoints.
+    pub fn from_metadata(metadata: Metadata) -> Result<Self, ParseError> {
+        let auth_uri = metadata.authorization_endpoint.clone()
+            .ok_or(ParseError::ExpectedFieldType("authorization_endpoint", "URL"))?;
+        let token_uri = metadata.token_endpoint.clone()
+            .ok_or(ParseError::ExpectedFieldType("token_endpoint", "URL"))?;
+        Ok(DiscoveredProvider {
+            metadata,
+            auth_uri,
+            token_uri,
+            lifetime: PhantomData,
+        })
+    }
+
+    /// Fetches authorization server metadata for an issuer and creates a provider from it.
+    ///
+    /// Tries the RFC 8414 well-known URI first, then the OpenID Connect one. The issuer in the
+    /// metadata must be identical to the requested issuer.
+    ///
+    /// See [RFC 8414, section 3](https://tools.ietf.org/html/rfc8414#section-3).
+    pub fn discover<H: HttpClient>(http_client: &H, issuer: &str) -> Result<Self, ClientError> {
+        let issuer_uri = Url::parse(issuer)?;
+
+        for uri in well_known_uris(&issuer_uri).iter() {
+            let response = http_client.execute(HttpRequest::get(uri.clone()))?;
+            if !response.is_success() {
+                continue;
+            }
+
+            let json = serde_json::from_slice(&response.body)?;
+            let metadata = Metadata::from_response(&json)?;
+            if metadata.issuer.as_str().trim_end_matches('/') != issuer.trim_end_matches('/') {
+                return Err(ClientError::from(ParseError::ExpectedFieldValue("issuer", "requested issuer")));
+            }
+            return Ok(DiscoveredProvider::from_metadata(metadata)?);
+        }
+
+        Err(ClientError::UnsupportedEndpoint("metadata"))
+    }
+
+    /// Returns the authorization server metadata.
+    pub fn metadata(&self) -> &Metadata { &self.metadata }
+
+    /// Returns the JSON Web Key Set document URI, if available.
+    pub fn jwks_uri(&self) -> Option<&Url> { self.metadata.jwks_uri.as_ref() }
+
+    /// Returns true if the provider supports the grant type.
+    pub fn supports_grant_type(&self, grant_type: &str) -> bool {
+        self.metadata.grant_types_supported.iter().any(|s| s == grant_type)
+    }
+
+    /// Returns true if the provider supports the client authentication method.
+    pub fn supports_auth_method(&self, method: &str) -> bool {
+        self.metadata.token_endpoint_auth_methods_supported.iter().any(|s| s == method)
+    }
+}
+
+impl<L: Lifetime> Provider for DiscoveredProvider<L> {
+    type Lifetime = L;
+    type Token = Bearer<L>;
+    fn issuer(&self) -> Option<&Url> { Some(&self.metadata.issuer) }
+    fn auth_uri(&self) -> &Url { &self.auth_uri }
+    fn token_uri(&self) -> &Url { &self.token_uri }
+    fn device_authorization_uri(&self) -> Option<&Url> {
+        self.metadata.device_authorization_endpoint.as_ref()
+    }
+    fn revocation_uri(&self) -> Option<&Url> { self.metadata.revocation_endpoint.as_ref() }
+    fn pushed_authorization_request_uri(&self) -> Option<&Url> {
+        self.metadata.pushed_authorization_request_endpoint.as_ref()
+    }
+    fn introspection_uri(&self) -> Option<&Url> { self.metadata.introspection_endpoint.as_ref() }
+    fn client_auth(&self) -> ClientAuth {
+        if !self.supports_auth_method("client_secret_basic")
+            && self.supports_auth_method("client_secret_post") {
+            ClientAuth::ClientSecretPost
+        } else {
+            ClientAuth::ClientSecretBasic
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use client::{ClientAuth, ClientError};
+    use client::http::{HttpResponse, MemoryClient, Method};
+    use client::response::{FromResponse, ParseError};
+    use provider::Provider;
+    use token::Static;
+    use super::{DiscoveredProvider, Metadata, well_known_uris};
+
+    #[test]
+    fn well_known() {
+        let [oauth, openid] = well_known_uris(&Url::parse("https://example.com").unwrap());
+        assert_eq!("https://example.com/.well-known/oauth-authorization-server", oauth.as_str());
+        assert_eq!("https://example.com/.well-known/openid-configuration", openid.as_str());
+    }
+
+    #[test]
+    fn well_known_with_path() {
+        let issuer = Url::parse("https://example.com/issuer1").unwrap();
+        let [oauth, openid] = well_known_uris(&issuer);
+        assert_eq!(
+            "https://example.com/.well-known/oauth-authorization-server/issuer1",
+            oauth.as_str()
+        );
+        assert_eq!(
+            "https://example.com/issuer1/.well-known/openid-configuration",
+            openid.as_str()
+        );
+    }
+
+    #[test]
+    fn from_response_empty() {
+        let json = "{}".parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("issuer", "URL"),
+            Metadata::from_response(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn from_response_defaults() {
+        let json = r#"{"issuer":"https://example.com"}"#.parse().unwrap();
+        let metadata = Metadata::from_response(&json).unwrap();
+        assert_eq!(None, metadata.token_endpoint);
+        assert_eq!(vec!["authorization_code", "implicit"], metadata.grant_types_supported);
+        assert_eq!(vec!["client_secret_basic"], metadata.token_endpoint_auth_methods_supported);
+        assert!(metadata.code_challenge_methods_supported.is_empty());
+        assert!(!metadata.require_pushed_authorization_requests);
+    }
+
+    #[test]
+    fn provider() {
+        let json = r#"
+            {
+                "issuer":"https://example.com",
+                "authorization_endpoint":"https://example.com/authorize",
+                "token_endpoint":"https://example.com/token",
+                "revocation_endpoint":"https://example.com/revoke",
+                "jwks_uri":"https://example.com/jwks.json",
+                "pushed_authorization_request_endpoint":"https://example.com/par",
+                "require_pushed_authorization_requests":true,
+                "grant_types_supported":["authorization_code","client_credentials"],
+                "token_endpoint_auth_methods_supported":["client_secret_post"]
+            }
+        "#.parse().unwrap();
+        let metadata = Metadata::from_response(&json).unwrap();
+        assert!(metadata.require_pushed_authorization_requests);
+        let provider = DiscoveredProvider::<Static>::from_metadata(metadata).unwrap();
+        assert_eq!("https://example.com/", provider.issuer().unwrap().as_str());
+        assert_eq!("https://example.com/authorize", provider.auth_uri().as_str());
+        assert_eq!("https://example.com/token", provider.token_uri().as_str());
+        assert_eq!("https://example.com/revoke", provider.revocation_uri().unwrap().as_str());
+        assert_eq!(None, provider.introspection_uri());
+        assert_eq!(None, provider.device_authorization_uri());
+        assert_eq!(
+            "https://example.com/par",
+            provider.pushed_authorization_request_uri().unwrap().as_str()
+        );
+        assert_eq!("https://example.com/jwks.json", provider.jwks_uri().unwrap().as_str());
+        assert!(provider.supports_grant_type("client_credentials"));
+        assert!(!provider.supports_grant_type("password"));
+        assert_eq!(ClientAuth::ClientSecretPost, provider.client_auth());
+    }
+
+    #[test]
+    fn provider_without_token_endpoint() {
+        let json = r#"
+            {
+                "issuer":"https://example.com",
+                "authorization_endpoint":"https://example.com/authorize"
+            }
+        "#.parse().unwrap();
+        let metadata = Metadata::from_response(&json).unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("token_endpoint", "URL"),
+            DiscoveredProvider::<Static>::from_metadata(metadata).unwrap_err()
+        );
+    }
+
+    const METADATA: &str = r#"{"issuer":"https://example.com","authorization_endpoint":"https://example.com/authorize","token_endpoint":"https://example.com/token"}"#;
+
+    fn not_found() -> HttpResponse {
+        HttpResponse { status: 404, headers: vec![], body: b"Not Found".to_vec() }
+    }
+
+    #[test]
+    fn discover() {
+        let http = MemoryClient::new();
+        http.push_json(200, METADATA);
+
+        let provider = DiscoveredProvider::<Static>::discover(&http, "https://example.com").unwrap();
+        assert_eq!("https://example.com/token", provider.token_uri().as_str());
+
+        let requests = http.requests();
+        assert_eq!(1, requests.len());
+        assert_eq!(Method::Get, requests[0].method);
+        assert_eq!(
+            "https://example.com/.well-known/oauth-authorization-server",
+            requests[0].url.as_str()
+        );
+    }
+
+    #[test]
+    fn discover_openid_configuration() {
+        let http = MemoryClient::new();
+        http.push(not_found());
+        http.push_json(200, METADATA);
+
+        let provider = DiscoveredProvider::<Static>::discover(&http, "https://example.com/").unwrap();
+        assert_eq!("https://example.com/authorize", provider.auth_uri().as_str());
+
+        let requests = http.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!(
+            "https://example.com/.well-known/openid-configuration",
+            requests[1].url.as_str()
+        );
+    }
+
+    #[test]
+    fn discover_not_found() {
+        let http = MemoryClient::new();
+        http.push(not_found());
+        http.push(not_found());
+
+        match DiscoveredProvider::<Static>::discover(&http, "https://example.com") {
+            Err(ClientError::UnsupportedEndpoint("metadata")) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn discover_issuer_mismatch() {
+        let http = MemoryClient::new();
+        http.push_json(200, METADATA);
+
+        match DiscoveredProvider::<Static>::discover(&http, "https://evil.example.com") {
+            Err(ClientError::Parse(ParseError::ExpectedFieldValue("issuer", _))) => {},
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(1, http.requests().len());
+    }
+}
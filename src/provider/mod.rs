@@ -0,0 +1,400 @@
+//! Providers.
+
+pub mod discovery;
+
+use url::Url;
+
+use client::ClientAuth;
+use scope::DEFAULT_DELIMITER;
+use token::{Token, Lifetime, Bearer, Static, Refresh};
+
+/// OAuth 2.0 providers.
+pub trait Provider {
+    /// The lifetime of tokens issued by the provider.
+    type Lifetime: Lifetime;
+
+    /// The type of token issued by the provider.
+    type Token: Token<Self::Lifetime>;
+
+    /// The issuer identifier, if known.
+    ///
+    /// See [RFC 8414, section 2](https://tools.ietf.org/html/rfc8414#section-2).
+    fn issuer(&self) -> Option<&Url> { None }
+
+    /// The authorization endpoint URI.
+    ///
+    /// See [RFC 6749, section 3.1](http://tools.ietf.org/html/rfc6749#section-3.1).
+    fn auth_uri(&self) -> &Url;
+
+    /// The token endpoint URI.
+    ///
+    /// See [RFC 6749, section 3.2](http://tools.ietf.org/html/rfc6749#section-3.2).
+    fn token_uri(&self) -> &Url;
+
+    /// The device authorization endpoint URI, if supported.
+    ///
+    /// See [RFC 8628, section 3.1](https://tools.ietf.org/html/rfc8628#section-3.1).
+    fn device_authorization_uri(&self) -> Option<&Url> { None }
+
+    /// The token revocation endpoint URI, if supported.
+    ///
+    /// See [RFC 7009, section 2](https://tools.ietf.org/html/rfc7009#section-2).
+    fn revocation_uri(&self) -> Option<&Url> { None }
+
+    /// The token introspection endpoint URI, if supported.
+    ///
+    /// See [RFC 7662, section 2](https://tools.ietf.org/html/rfc7662#section-2).
+    fn introspection_uri(&self) -> Option<&Url> { None }
+
+    /// The pushed authorization request endpoint URI, if supported.
+    ///
+    /// See [RFC 9126, section 2](https://tools.ietf.org/html/rfc9126#section-2).
+    fn pushed_authorization_request_uri(&self) -> Option<&Url> { None }
+
+    /// Default client authentication method.
+    ///
+    /// Although not recommended by the RFC, some providers require `client_id` and `client_secret`
+    /// as part of the request body, using `ClientAuth::ClientSecretPost`.
+    ///
+    /// See [RFC 6749, section 2.3.1](http://tools.ietf.org/html/rfc6749#section-2.3.1).
+    fn client_auth(&self) -> ClientAuth { ClientAuth::ClientSecretBasic }
+
+    /// Delimiter of scope tokens in the `scope` parameter.
+    ///
+    /// Although RFC 6749 specifies spaces, some providers, such as GitHub, use commas.
+    ///
+    /// See [RFC 6749, section 3.3](http://tools.ietf.org/html/rfc6749#section-3.3).
+    fn scope_delimiter(&self) -> char { DEFAULT_DELIMITER }
+
+    /// Provider requires PKCE.
+    ///
+    /// Clients of such providers must use `Client::auth_uri_with_challenge` and
+    /// `Client::request_token_with_verifier`. Token, pushed authorization and request object
+    /// requests without PKCE return `ClientError::PkceRequired`.
+    ///
+    /// See [RFC 7636](https://tools.ietf.org/html/rfc7636).
+    fn pkce_required(&self) -> bool { false }
+}
+
+/// Google OAuth 2.0 providers.
+///
+/// See [Using OAuth 2.0 to Access Google
+/// APIs](https://developers.google.com/identity/protocols/OAuth2).
+pub mod google {
+    use std::fs;
+    use std::path::Path;
+
+    use serde_json;
+    use url::Url;
+
+    use client::assertion::Assertion;
+    use client::response::ParseError;
+    use client::{ClientAuth, ClientError};
+    use jwt::SigningKey;
+    use secret::Secret;
+    use token::{Bearer, Expiring, OidcBearer, Refresh};
+    use super::Provider;
+
+    /// Issuer identifier of Google ID tokens.
+    ///
+    /// See [OpenID Connect](https://developers.google.com/identity/protocols/OpenIDConnect).
+    pub const ISSUER: &str = "https://accounts.google.com";
+
+    /// URI of the key set used to sign Google ID tokens.
+    ///
+    /// See [OpenID Connect](https://developers.google.com/identity/protocols/OpenIDConnect).
+    pub const JWKS_URI: &str = "https://www.googleapis.com/oauth2/v3/certs";
+
+    /// Signals the server to return the authorization code by prompting the user to copy and
+    /// paste.
+    ///
+    /// See [Choosing a redirect URI][uri].
+    ///
+    /// [uri]: https://developers.google.com/identity/protocols/OAuth2InstalledApp#choosingredirecturi
+    #[deprecated(note = "Google no longer supports the out-of-band flow; use `LoopbackListener`")]
+    pub const REDIRECT_URI_OOB: &str = "urn:ietf:wg:oauth:2.0:oob";
+
+    /// Signals the server to return the authorization code in the page title.
+    ///
+    /// See [Choosing a redirect URI][uri].
+    ///
+    /// [uri]: https://developers.google.com/identity/protocols/OAuth2InstalledApp#choosingredirecturi
+    #[deprecated(note = "Google no longer supports the out-of-band flow; use `LoopbackListener`")]
+    pub const REDIRECT_URI_OOB_AUTO: &str = "urn:ietf:wg:oauth:2.0:oob:auto";
+
+    lazy_static! {
+        static ref AUTH_URI: Url = Url::parse("https://accounts.google.com/o/oauth2/v2/auth").unwrap();
+        static ref TOKEN_URI: Url = Url::parse("https://www.googleapis.com/oauth2/v4/token").unwrap();
+        static ref REVOCATION_URI: Url = Url::parse("https://oauth2.googleapis.com/revoke").unwrap();
+        static ref SERVICE_ACCOUNT_TOKEN_URI: Url = Url::parse("https://oauth2.googleapis.com/token").unwrap();
+    }
+
+    /// Google OAuth 2.0 provider for web applications.
+    ///
+    /// Tokens include an ID token if the `openid` scope is requested.
+    ///
+    /// The token type was previously `Bearer<Expiring>`. Code naming it must use
+    /// `OidcBearer<Expiring>` instead; tokens stored as `Bearer` still deserialize.
+    ///
+    /// See [Using OAuth 2.0 for Web Server
+    /// Applications](https://developers.google.com/identity/protocols/OAuth2WebServer).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Web;
+    impl Provider for Web {
+        type Lifetime = Expiring;
+        type Token = OidcBearer<Expiring>;
+        fn auth_uri(&self) -> &Url { &AUTH_URI }
+        fn token_uri(&self) -> &Url { &TOKEN_URI }
+        fn revocation_uri(&self) -> Option<&Url> { Some(&REVOCATION_URI) }
+    }
+
+    /// Google OAuth 2.0 provider for installed applications.
+    ///
+    /// Tokens include an ID token if the `openid` scope is requested.
+    ///
+    /// The token type was previously `Bearer<Refresh>`. Code naming it must use
+    /// `OidcBearer<Refresh>` instead; tokens stored as `Bearer` still deserialize.
+    ///
+    /// See [Using OAuth 2.0 for Installed
+    /// Applications](https://developers.google.com/identity/protocols/OAuth2InstalledApp).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Installed;
+    impl Provider for Installed {
+        type Lifetime = Refresh;
+        type Token = OidcBearer<Refresh>;
+        fn auth_uri(&self) -> &Url { &AUTH_URI }
+        fn token_uri(&self) -> &Url { &TOKEN_URI }
+        fn revocation_uri(&self) -> Option<&Url> { Some(&REVOCATION_URI) }
+        fn pkce_required(&self) -> bool { true }
+    }
+
+    /// Google OAuth 2.0 provider for service accounts.
+    ///
+    /// Tokens are requested with a JWT assertion signed by a `ServiceAccountKey`, without user
+    /// interaction, and there is no client secret or authorization endpoint.
+    ///
+    /// See [Using OAuth 2.0 for Server to Server
+    /// Applications](https://developers.google.com/identity/protocols/OAuth2ServiceAccount).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate inth_oauth2;
+    /// # extern crate reqwest;
+    /// use inth_oauth2::Client;
+    /// use inth_oauth2::provider::google::{ServiceAccount, ServiceAccountKey};
+    ///
+    /// # fn main() {
+    /// let key = ServiceAccountKey::from_file("service-account.json").unwrap();
+    /// let assertion = key
+    ///     .sign_assertion("https://www.googleapis.com/auth/devstorage.read_only")
+    ///     .unwrap();
+    ///
+    /// let client = Client::new(ServiceAccount, String::new(), String::new(), None);
+    /// let http = reqwest::Client::new();
+    /// let token = client.request_assertion_token(&http, &assertion, None).unwrap();
+    /// # }
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ServiceAccount;
+    impl Provider for ServiceAccount {
+        type Lifetime = Expiring;
+        type Token = Bearer<Expiring>;
+        fn auth_uri(&self) -> &Url { &AUTH_URI }
+        fn token_uri(&self) -> &Url { &SERVICE_ACCOUNT_TOKEN_URI }
+        fn revocation_uri(&self) -> Option<&Url> { Some(&REVOCATION_URI) }
+        fn client_auth(&self) -> ClientAuth { ClientAuth::None }
+    }
+
+    /// Google service account key, in the JSON format downloaded from the Cloud Console.
+    ///
+    /// `Debug` does not show the private key.
+    #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+    pub struct ServiceAccountKey {
+        /// Project ID.
+        #[serde(default)]
+        pub project_id: Option<String>,
+
+        /// ID of the private key, sent as the `kid` of assertions.
+        pub private_key_id: String,
+
+        private_key: Secret,
+
+        /// Email address of the service account, the issuer of assertions.
+        pub client_email: String,
+
+        /// Client ID of the service account.
+        #[serde(default)]
+        pub client_id: Option<String>,
+
+        /// Token endpoint URI, the audience of assertions.
+        pub token_uri: String,
+
+        #[serde(rename = "type")]
+        key_type: String,
+    }
+
+    impl ServiceAccountKey {
+        /// Parses a service account key.
+        pub fn from_json(json: &str) -> Result<Self, ClientError> {
+            let key: ServiceAccountKey = serde_json::from_str(json)?;
+            if key.key_type != "service_account" {
+                return Err(ClientError::from(
+                    ParseError::ExpectedFieldValue("type", "service_account"),
+                ));
+            }
+            Ok(key)
+        }
+
+        /// Reads a service account key file.
+        pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ClientError> {
+            ServiceAccountKey::from_json(&fs::read_to_string(path)?)
+        }
+
+        /// Returns the RS256 key signing assertions.
+        pub fn signing_key(&self) -> Result<SigningKey, ClientError> {
+            let key = SigningKey::from_pem(self.private_key.secret().as_bytes())?;
+            Ok(key.kid(self.private_key_id.clone()))
+        }
+
+        /// Returns an assertion requesting a scope for the service account.
+        ///
+        /// For domain-wide delegation, set the subject to the user to impersonate.
+        pub fn assertion(&self, scope: &str) -> Assertion {
+            Assertion::new(&self.client_email, &self.token_uri).scope(scope)
+        }
+
+        /// Signs an assertion requesting a scope for the service account.
+        pub fn sign_assertion(&self, scope: &str) -> Result<String, ClientError> {
+            self.assertion(scope).sign(&self.signing_key()?)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use client::Client;
+        use client::assertion::JWT_BEARER_GRANT_TYPE;
+        use client::http::MemoryClient;
+        use jwt::tests::{jwk, rsa_key};
+        use jwt::{Jwks, Jwt};
+        use token::Token;
+        use url::form_urlencoded;
+        use super::{ServiceAccount, ServiceAccountKey};
+
+        fn key_json(pem: &str) -> String {
+            json!({
+                "type": "service_account",
+                "project_id": "project",
+                "private_key_id": "1",
+                "private_key": pem,
+                "client_email": "account@project.iam.gserviceaccount.com",
+                "client_id": "1234",
+                "auth_uri": "https://accounts.google.com/o/oauth2/auth",
+                "token_uri": "https://oauth2.googleapis.com/token",
+            }).to_string()
+        }
+
+        #[test]
+        fn service_account_token() {
+            let private_key = rsa_key();
+            let pem = String::from_utf8(private_key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+            let key = ServiceAccountKey::from_json(&key_json(&pem)).unwrap();
+            assert!(!format!("{:?}", key).contains("PRIVATE"));
+
+            let http = MemoryClient::new();
+            http.push_json(200, r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":3599}"#);
+            let client = Client::new(ServiceAccount, String::new(), String::new(), None);
+            let assertion = key.sign_assertion("https://www.googleapis.com/auth/cloud-platform")
+                .unwrap();
+            let token = client.request_assertion_token(&http, &assertion, None).unwrap();
+            assert_eq!("aaaaaaaa", token.access_token());
+
+            let request = &http.requests()[0];
+            assert_eq!(None, request.header("authorization"));
+            let body: Vec<(String, String)> = form_urlencoded::parse(&request.body)
+                .into_owned()
+                .collect();
+            assert_eq!(2, body.len());
+            assert_eq!(("grant_type".into(), JWT_BEARER_GRANT_TYPE.into()), body[0]);
+
+            let jwt = Jwt::decode(&body[1].1).unwrap();
+            jwt.verify(&Jwks { keys: vec![jwk(&private_key, "1")] }).unwrap();
+            let claims = jwt.claims();
+            assert_eq!(Some(&json!("account@project.iam.gserviceaccount.com")), claims.get("iss"));
+            assert_eq!(Some(&json!("https://oauth2.googleapis.com/token")), claims.get("aud"));
+            assert_eq!(
+                Some(&json!("https://www.googleapis.com/auth/cloud-platform")),
+                claims.get("scope")
+            );
+        }
+
+        #[test]
+        fn service_account_key_wrong_type() {
+            let json = key_json("").replace("service_account", "authorized_user");
+            assert!(ServiceAccountKey::from_json(&json).is_err());
+        }
+    }
+}
+
+lazy_static! {
+    static ref GITHUB_AUTH_URI: Url = Url::parse("https://github.com/login/oauth/authorize").unwrap();
+    static ref GITHUB_TOKEN_URI: Url = Url::parse("https://github.com/login/oauth/access_token").unwrap();
+    static ref IMGUR_AUTH_URI: Url = Url::parse("https://api.imgur.com/oauth2/authorize").unwrap();
+    static ref IMGUR_TOKEN_URI: Url = Url::parse("https://api.imgur.com/oauth2/token").unwrap();
+}
+
+/// GitHub OAuth 2.0 provider.
+///
+/// See [OAuth, GitHub Developer Guide](https://developer.github.com/v3/oauth/).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GitHub;
+impl Provider for GitHub {
+    type Lifetime = Static;
+    type Token = Bearer<Static>;
+    fn auth_uri(&self) -> &Url { &GITHUB_AUTH_URI }
+    fn token_uri(&self) -> &Url { &GITHUB_TOKEN_URI }
+    fn scope_delimiter(&self) -> char { ',' }
+}
+
+/// Imgur OAuth 2.0 provider.
+///
+/// See [OAuth 2.0, Imgur](https://api.imgur.com/oauth2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Imgur;
+impl Provider for Imgur {
+    type Lifetime = Refresh;
+    type Token = Bearer<Refresh>;
+    fn auth_uri(&self) -> &Url { &IMGUR_AUTH_URI }
+    fn token_uri(&self) -> &Url { &IMGUR_TOKEN_URI }
+}
+
+#[test]
+fn google_urls() {
+    let prov = google::Web;
+    prov.auth_uri();
+    prov.token_uri();
+    prov.revocation_uri().unwrap();
+    let prov = google::Installed;
+    prov.auth_uri();
+    prov.token_uri();
+    prov.revocation_uri().unwrap();
+    let prov = google::ServiceAccount;
+    prov.auth_uri();
+    prov.token_uri();
+    prov.revocation_uri().unwrap();
+}
+
+#[test]
+fn github_urls() {
+    let prov = GitHub;
+    prov.auth_uri();
+    prov.token_uri();
+    assert_eq!(',', prov.scope_delimiter());
+}
+
+#[test]
+fn imgur_urls() {
+    let prov = Imgur;
+    prov.auth_uri();
+    prov.token_uri();
+}
\ No newline at end of file
@@ -0,0 +1,212 @@
+//! Access token scope.
+//!
+//! See [RFC 6749, section 3.3](http://tools.ietf.org/html/rfc6749#section-3.3).
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::slice;
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// Delimiter of scope tokens in the RFC 6749 format.
+pub const DEFAULT_DELIMITER: char = ' ';
+
+/// A set of scope tokens, in the order first added.
+///
+/// Displays and serializes in the RFC 6749 format, space-delimited.
+///
+/// # Examples
+///
+/// ```
+/// use inth_oauth2::scope::Scope;
+///
+/// let requested = Scope::from("repo gist user");
+/// let granted = Scope::parse("repo,gist", ',');
+/// assert!(granted.contains("gist"));
+/// assert!(granted.is_subset(&requested));
+/// assert_eq!("user", requested.difference(&granted).to_string());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    tokens: Vec<String>,
+}
+
+impl Scope {
+    /// Creates an empty scope.
+    pub fn new() -> Self {
+        Scope::default()
+    }
+
+    /// Parses scope tokens separated by a delimiter, ignoring surrounding whitespace and empty
+    /// tokens.
+    pub fn parse(scope: &str, delimiter: char) -> Self {
+        scope.split(delimiter)
+            .flat_map(str::split_whitespace)
+            .collect()
+    }
+
+    /// Adds a scope token, returning false if it was already present.
+    pub fn insert(&mut self, token: &str) -> bool {
+        if self.contains(token) {
+            false
+        } else {
+            self.tokens.push(token.into());
+            true
+        }
+    }
+
+    /// Returns true if the scope contains a scope token.
+    pub fn contains(&self, token: &str) -> bool {
+        self.tokens.iter().any(|t| t == token)
+    }
+
+    /// Returns true if every scope token of this scope is in `other`.
+    pub fn is_subset(&self, other: &Scope) -> bool {
+        self.iter().all(|token| other.contains(token))
+    }
+
+    /// Returns true if every scope token of `other` is in this scope.
+    pub fn is_superset(&self, other: &Scope) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns the scope tokens of this scope not in `other`.
+    pub fn difference(&self, other: &Scope) -> Scope {
+        self.iter().filter(|token| !other.contains(token)).collect()
+    }
+
+    /// Returns the number of scope tokens.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns true if the scope has no scope tokens.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns an iterator over the scope tokens.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.tokens.iter())
+    }
+
+    /// Joins the scope tokens with a delimiter.
+    pub fn join(&self, delimiter: char) -> String {
+        let mut joined = String::new();
+        for token in self.iter() {
+            if !joined.is_empty() {
+                joined.push(delimiter);
+            }
+            joined.push_str(token);
+        }
+        joined
+    }
+}
+
+/// Scopes are equal if they contain the same scope tokens, in any order.
+impl PartialEq for Scope {
+    fn eq(&self, other: &Scope) -> bool {
+        self.len() == other.len() && self.is_subset(other)
+    }
+}
+
+impl Eq for Scope {}
+
+impl From<&str> for Scope {
+    fn from(scope: &str) -> Self {
+        Scope::parse(scope, DEFAULT_DELIMITER)
+    }
+}
+
+impl<'a> FromIterator<&'a str> for Scope {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut scope = Scope::new();
+        for token in iter {
+            scope.insert(token);
+        }
+        scope
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.join(DEFAULT_DELIMITER))
+    }
+}
+
+impl<'a> IntoIterator for &'a Scope {
+    type Item = &'a str;
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Iter<'a> { self.iter() }
+}
+
+/// Iterator over scope tokens.
+#[derive(Debug, Clone)]
+pub struct Iter<'a>(slice::Iter<'a, String>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<&'a str> { self.0.next().map(|s| &s[..]) }
+}
+
+impl Serialize for Scope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Scope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let scope = String::deserialize(deserializer)?;
+        Ok(Scope::from(&scope[..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::Scope;
+
+    #[test]
+    fn parse() {
+        let scope = Scope::from(" a  b a ");
+        assert_eq!(vec!["a", "b"], scope.iter().collect::<Vec<_>>());
+        assert_eq!("a b", scope.to_string());
+        assert!(Scope::from("").is_empty());
+    }
+
+    #[test]
+    fn parse_delimiter() {
+        let scope = Scope::parse("repo, gist,,user", ',');
+        assert_eq!(vec!["repo", "gist", "user"], scope.iter().collect::<Vec<_>>());
+        assert_eq!("repo,gist,user", scope.join(','));
+    }
+
+    #[test]
+    fn eq_unordered() {
+        assert_eq!(Scope::from("a b"), Scope::from("b a"));
+        assert_ne!(Scope::from("a b"), Scope::from("a"));
+    }
+
+    #[test]
+    fn subset() {
+        let requested = Scope::from("a b c");
+        let granted = Scope::from("a c");
+        assert!(granted.is_subset(&requested));
+        assert!(!requested.is_subset(&granted));
+        assert!(requested.is_superset(&granted));
+        assert!(Scope::new().is_subset(&granted));
+        assert_eq!(Scope::from("b"), requested.difference(&granted));
+        assert!(granted.difference(&requested).is_empty());
+    }
+
+    #[test]
+    fn serde() {
+        let scope = Scope::from("a b");
+        let json = serde_json::to_string(&scope).unwrap();
+        assert_eq!(r#""a b""#, json);
+        assert_eq!(scope, serde_json::from_str(&json).unwrap());
+    }
+}
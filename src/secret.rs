@@ -0,0 +1,131 @@
+//! Secret strings.
+
+use std::fmt;
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{self, Ordering};
+
+use openssl::memcmp;
+
+/// A secret string, such as a client secret or token.
+///
+/// `Debug` and `Display` are redacted, so secrets are not leaked by formatting the structures
+/// containing them. The value is read explicitly with `secret`, and is overwritten with zeros
+/// when dropped. Copies made before the secret was constructed, such as by reallocating a
+/// `String`, are not zeroed.
+///
+/// Serializes and deserializes as a plain string.
+///
+/// # Examples
+///
+/// ```
+/// use inth_oauth2::secret::Secret;
+///
+/// let secret = Secret::from("hunter2");
+/// assert_eq!("[redacted]", format!("{:?}", secret));
+/// assert_eq!("hunter2", secret.secret());
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wraps a secret string.
+    pub fn new(secret: String) -> Self {
+        Secret(secret)
+    }
+
+    /// Returns the secret value.
+    pub fn secret(&self) -> &str { &self.0 }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Secret(secret.into())
+    }
+}
+
+/// Compares in constant time for values of equal length.
+impl PartialEq for Secret {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && memcmp::eq(self.0.as_bytes(), other.0.as_bytes())
+    }
+}
+
+impl Eq for Secret {}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str("[redacted]")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str("[redacted]")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        unsafe { zero(self.0.as_mut_vec()) }
+    }
+}
+
+/// Secret bytes, such as key material, overwritten with zeros when dropped.
+pub(crate) struct SecretBytes<T: AsMut<[u8]>>(pub(crate) T);
+
+impl<T: AsMut<[u8]>> Deref for SecretBytes<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<T: AsMut<[u8]>> Drop for SecretBytes<T> {
+    fn drop(&mut self) {
+        zero(self.0.as_mut());
+    }
+}
+
+/// Overwrites bytes with zeros.
+fn zero(bytes: &mut [u8]) {
+    // Volatile writes are not elided even though the memory is about to be freed.
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::Secret;
+
+    #[test]
+    fn redacted() {
+        let secret = Secret::from("hunter2");
+        assert_eq!("[redacted]", format!("{:?}", secret));
+        assert_eq!("[redacted]", format!("{}", secret));
+        assert_eq!("hunter2", secret.secret());
+    }
+
+    #[test]
+    fn eq() {
+        assert_eq!(Secret::from("hunter2"), Secret::from("hunter2"));
+        assert_ne!(Secret::from("hunter2"), Secret::from("hunter3"));
+        assert_ne!(Secret::from("hunter2"), Secret::from("hunter22"));
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let json = serde_json::to_string(&Secret::from("hunter2")).unwrap();
+        assert_eq!(r#""hunter2""#, json);
+        assert_eq!("hunter2", serde_json::from_str::<Secret>(&json).unwrap().secret());
+    }
+}
@@ -0,0 +1,332 @@
+//! Encrypted token file storage.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use openssl::hash::MessageDigest;
+use openssl::pkcs5::pbkdf2_hmac;
+use openssl::rand::rand_bytes;
+use openssl::symm::{Cipher, decrypt_aead, encrypt_aead};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use client::ClientError;
+use secret::SecretBytes;
+use super::{FileStore, StoreKey, TokenStore};
+
+const MAGIC: &[u8; 4] = b"IOT\0";
+const VERSION: u8 = 1;
+const ALG_AES_256_GCM: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const HEADER_LEN: usize = 4 + 1 + 1 + 4 + SALT_LEN + NONCE_LEN;
+
+/// Default PBKDF2 iterations for passphrases.
+const PASSPHRASE_ITERATIONS: u32 = 600_000;
+
+/// Maximum PBKDF2 iterations, so that a tampered file cannot make loading it take hours.
+const MAX_ITERATIONS: u32 = 10_000_000;
+
+/// Minimum key file length in bytes.
+const KEY_FILE_MIN_LEN: usize = 32;
+
+/// Encrypted token file storage.
+///
+/// Tokens are serialized as JSON and encrypted with AES-256-GCM, under a key derived with
+/// PBKDF2-HMAC-SHA256 from a passphrase or the contents of a key file and a random salt. Each
+/// file begins with a header recording the format version, algorithm, iteration count, salt and
+/// nonce, which is authenticated along with the store key, so files cannot be swapped between
+/// keys.
+///
+/// Files are otherwise stored as by `FileStore`. The passphrase or key file contents, derived
+/// keys and decrypted tokens are overwritten with zeros once no longer needed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use inth_oauth2::store::encrypted::EncryptedFileStore;
+///
+/// let store = EncryptedFileStore::with_key_file("tokens", "tokens.key").unwrap();
+/// ```
+pub struct EncryptedFileStore {
+    files: FileStore,
+    secret: SecretBytes<Vec<u8>>,
+    iterations: u32,
+}
+
+impl EncryptedFileStore {
+    /// Creates a store in a directory, with keys derived from a passphrase.
+    pub fn with_passphrase<D: Into<PathBuf>>(dir: D, passphrase: &str) -> Self {
+        EncryptedFileStore {
+            files: FileStore::with_extension(dir.into(), "enc"),
+            secret: SecretBytes(passphrase.as_bytes().to_vec()),
+            iterations: PASSPHRASE_ITERATIONS,
+        }
+    }
+
+    /// Creates a store in a directory, with keys derived from the contents of a key file.
+    ///
+    /// The key file must contain at least 32 bytes, which should be random.
+    pub fn with_key_file<D, K>(dir: D, key_file: K) -> io::Result<Self>
+    where D: Into<PathBuf>, K: AsRef<Path> {
+        let mut file = File::open(key_file)?;
+        // Reserve the whole file up front, so that reading does not reallocate and leave copies.
+        let len = file.metadata()?.len() as usize;
+        let mut secret = SecretBytes(Vec::with_capacity(len + 1));
+        file.read_to_end(&mut secret.0)?;
+        if secret.len() < KEY_FILE_MIN_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "key file too short"));
+        }
+        Ok(EncryptedFileStore {
+            files: FileStore::with_extension(dir.into(), "enc"),
+            secret,
+            iterations: 1,
+        })
+    }
+
+    /// Sets the PBKDF2 iteration count for newly saved tokens.
+    ///
+    /// Defaults to 600,000 for passphrases and 1 for key files, and is at most 10,000,000. Saved
+    /// tokens record their iteration count, so it can be changed without losing them.
+    pub fn iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations.clamp(1, MAX_ITERATIONS);
+        self
+    }
+
+    /// Returns the directory.
+    pub fn dir(&self) -> &Path { self.files.dir() }
+
+    /// Returns the path of the file for a key.
+    pub fn path(&self, key: &StoreKey) -> PathBuf { self.files.path(key) }
+
+    fn derive_key(
+        &self,
+        salt: &[u8],
+        iterations: u32,
+    ) -> Result<SecretBytes<[u8; 32]>, ClientError> {
+        let mut key = SecretBytes([0; 32]);
+        pbkdf2_hmac(
+            &self.secret,
+            salt,
+            iterations as usize,
+            MessageDigest::sha256(),
+            &mut key.0,
+        )?;
+        Ok(key)
+    }
+
+    fn encrypt(&self, store_key: &StoreKey, plaintext: &[u8]) -> Result<Vec<u8>, ClientError> {
+        let mut salt = [0; SALT_LEN];
+        let mut nonce = [0; NONCE_LEN];
+        rand_bytes(&mut salt)?;
+        rand_bytes(&mut nonce)?;
+
+        let mut file = Vec::with_capacity(HEADER_LEN + plaintext.len() + TAG_LEN);
+        file.extend_from_slice(MAGIC);
+        file.push(VERSION);
+        file.push(ALG_AES_256_GCM);
+        file.extend_from_slice(&self.iterations.to_be_bytes());
+        file.extend_from_slice(&salt);
+        file.extend_from_slice(&nonce);
+
+        let key = self.derive_key(&salt, self.iterations)?;
+        let aad = aad(&file, store_key);
+        let mut tag = [0; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &*key,
+            Some(&nonce),
+            &aad,
+            plaintext,
+            &mut tag,
+        )?;
+
+        file.extend_from_slice(&ciphertext);
+        file.extend_from_slice(&tag);
+        Ok(file)
+    }
+
+    fn decrypt(
+        &self,
+        store_key: &StoreKey,
+        file: &[u8],
+    ) -> Result<SecretBytes<Vec<u8>>, ClientError> {
+        if file.len() < HEADER_LEN + TAG_LEN || &file[..4] != MAGIC {
+            return Err(invalid_data("not an encrypted token file"));
+        }
+        if file[4] != VERSION || file[5] != ALG_AES_256_GCM {
+            return Err(invalid_data("unsupported encrypted token file version"));
+        }
+
+        let mut iterations = [0; 4];
+        iterations.copy_from_slice(&file[6..10]);
+        let iterations = u32::from_be_bytes(iterations);
+        let salt = &file[10..10 + SALT_LEN];
+        let nonce = &file[10 + SALT_LEN..HEADER_LEN];
+        let (ciphertext, tag) = file[HEADER_LEN..].split_at(file.len() - HEADER_LEN - TAG_LEN);
+        if iterations == 0 || iterations > MAX_ITERATIONS {
+            return Err(invalid_data("invalid iteration count"));
+        }
+
+        let key = self.derive_key(salt, iterations)?;
+        let aad = aad(&file[..HEADER_LEN], store_key);
+        decrypt_aead(Cipher::aes_256_gcm(), &*key, Some(nonce), &aad, ciphertext, tag)
+            .map(SecretBytes)
+            .map_err(|_| invalid_data("cannot decrypt token file"))
+    }
+}
+
+fn aad(header: &[u8], store_key: &StoreKey) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(store_key.file_name().as_bytes());
+    aad
+}
+
+fn invalid_data(message: &'static str) -> ClientError {
+    ClientError::from(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+impl fmt::Debug for EncryptedFileStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("EncryptedFileStore")
+            .field("dir", &self.files.dir())
+            .field("iterations", &self.iterations)
+            .finish()
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> TokenStore<T> for EncryptedFileStore {
+    fn load(&self, key: &StoreKey) -> Result<Option<T>, ClientError> {
+        match self.files.read(key)? {
+            Some(file) => Ok(Some(serde_json::from_slice(&self.decrypt(key, &file)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, key: &StoreKey, token: &T) -> Result<(), ClientError> {
+        let plaintext = SecretBytes(serde_json::to_vec(token)?);
+        let file = self.encrypt(key, &plaintext)?;
+        self.files.write(key, &file)
+    }
+
+    fn delete(&self, key: &StoreKey) -> Result<(), ClientError> {
+        self.files.remove(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+
+    use client::ClientError;
+    use client::response::FromResponse;
+    use store::tests::temp_dir;
+    use store::{StoreKey, TokenStore};
+    use token::{Bearer, Refresh};
+    use super::EncryptedFileStore;
+
+    fn token() -> Bearer<Refresh> {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":3600,"refresh_token":"bbbbbbbb"}"#
+            .parse()
+            .unwrap();
+        Bearer::from_response(&json).unwrap()
+    }
+
+    fn key() -> StoreKey {
+        StoreKey::new("https://example.com/token", "client", "user")
+    }
+
+    #[test]
+    fn passphrase_round_trip() {
+        let dir = temp_dir("encrypted-passphrase");
+        let store = EncryptedFileStore::with_passphrase(&dir, "hunter2").iterations(1000);
+        let token = token();
+
+        store.save(&key(), &token).unwrap();
+        let file = fs::read(store.path(&key())).unwrap();
+        assert_eq!(b"IOT\0\x01\x01", &file[..6]);
+        assert!(!String::from_utf8_lossy(&file).contains("bbbbbbbb"));
+
+        let store = EncryptedFileStore::with_passphrase(&dir, "hunter2");
+        assert_eq!(Some(token), store.load(&key()).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn key_file_round_trip() {
+        let dir = temp_dir("encrypted-key-file");
+        fs::create_dir_all(&dir).unwrap();
+        let key_file = dir.join("key");
+        fs::File::create(&key_file).unwrap().write_all(&[7; 32]).unwrap();
+
+        let store = EncryptedFileStore::with_key_file(dir.join("tokens"), &key_file).unwrap();
+        let token = token();
+        store.save(&key(), &token).unwrap();
+        assert_eq!(Some(token), store.load(&key()).unwrap());
+
+        TokenStore::<Bearer<Refresh>>::delete(&store, &key()).unwrap();
+        assert_eq!(None, TokenStore::<Bearer<Refresh>>::load(&store, &key()).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn key_file_too_short() {
+        let dir = temp_dir("encrypted-short-key");
+        fs::create_dir_all(&dir).unwrap();
+        let key_file = dir.join("key");
+        fs::File::create(&key_file).unwrap().write_all(b"short").unwrap();
+
+        assert!(EncryptedFileStore::with_key_file(&dir, &key_file).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase() {
+        let dir = temp_dir("encrypted-wrong-passphrase");
+        let store = EncryptedFileStore::with_passphrase(&dir, "hunter2").iterations(1000);
+        store.save(&key(), &token()).unwrap();
+
+        let store = EncryptedFileStore::with_passphrase(&dir, "hunter3");
+        match TokenStore::<Bearer<Refresh>>::load(&store, &key()) {
+            Err(ClientError::Io(_)) => {},
+            result => panic!("{:?}", result),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn excessive_iterations() {
+        let dir = temp_dir("encrypted-iterations");
+        let store = EncryptedFileStore::with_passphrase(&dir, "hunter2").iterations(1000);
+        store.save(&key(), &token()).unwrap();
+
+        let path = store.path(&key());
+        let mut file = fs::read(&path).unwrap();
+        file[6..10].copy_from_slice(&u32::MAX.to_be_bytes());
+        fs::write(&path, &file).unwrap();
+
+        match TokenStore::<Bearer<Refresh>>::load(&store, &key()) {
+            Err(ClientError::Io(ref err)) => assert_eq!("invalid iteration count", err.to_string()),
+            result => panic!("{:?}", result),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn swapped_file() {
+        let dir = temp_dir("encrypted-swapped");
+        let store = EncryptedFileStore::with_passphrase(&dir, "hunter2").iterations(1000);
+        let other = StoreKey::new("https://example.com/token", "client", "other");
+        store.save(&key(), &token()).unwrap();
+        fs::rename(store.path(&key()), store.path(&other)).unwrap();
+
+        assert!(TokenStore::<Bearer<Refresh>>::load(&store, &other).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
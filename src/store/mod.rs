@@ -0,0 +1,410 @@
+//! Persistent token storage.
+//!
+//! Tokens are stored as JSON under a `StoreKey` identifying the provider, client and user. Use
+//! `Client::refresh_token_stored` or `Client::ensure_token_stored` to save refreshed tokens
+//! automatically. If saving fails, the refreshed token is returned in
+//! `StoredRefreshError::Save`, so that a rotated refresh token is never lost.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use openssl::sha::sha256;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use client::ClientError;
+
+pub mod encrypted;
+
+/// Identifies a stored token.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoreKey {
+    /// Provider identifier, such as its token endpoint URI.
+    pub provider: String,
+
+    /// Client ID.
+    pub client_id: String,
+
+    /// User the token was issued for.
+    pub user: String,
+}
+
+impl StoreKey {
+    /// Creates a key.
+    pub fn new(provider: &str, client_id: &str, user: &str) -> Self {
+        StoreKey {
+            provider: provider.into(),
+            client_id: client_id.into(),
+            user: user.into(),
+        }
+    }
+
+    /// Returns a file name unique to the key, without an extension.
+    pub(crate) fn file_name(&self) -> String {
+        let mut input = Vec::new();
+        for part in &[&self.provider, &self.client_id, &self.user] {
+            input.extend_from_slice(part.as_bytes());
+            input.push(0);
+        }
+        sha256(&input).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Error refreshing a token and saving it to a store.
+#[derive(Debug)]
+pub enum StoredRefreshError<T> {
+    /// The token could not be refreshed. The stored token is unchanged.
+    Refresh(ClientError),
+
+    /// The token was refreshed but could not be saved.
+    ///
+    /// The refreshed token must be kept and saved again later, since the provider may have
+    /// invalidated the refresh token of the stored one.
+    Save(T, ClientError),
+}
+
+impl<T> StoredRefreshError<T> {
+    /// Returns the underlying error.
+    pub fn error(&self) -> &ClientError {
+        match *self {
+            StoredRefreshError::Refresh(ref err) | StoredRefreshError::Save(_, ref err) => err,
+        }
+    }
+
+    /// Returns the refreshed token, if the refresh succeeded.
+    pub fn into_token(self) -> Option<T> {
+        match self {
+            StoredRefreshError::Refresh(_) => None,
+            StoredRefreshError::Save(token, _) => Some(token),
+        }
+    }
+}
+
+impl<T> fmt::Display for StoredRefreshError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoredRefreshError::Refresh(ref err) => write!(f, "Token refresh failed: {}", err),
+            StoredRefreshError::Save(_, ref err) => {
+                write!(f, "Refreshed token could not be saved: {}", err)
+            },
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for StoredRefreshError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error())
+    }
+}
+
+/// Token storage.
+pub trait TokenStore<T> {
+    /// Loads a token, returning `None` if none is stored.
+    fn load(&self, key: &StoreKey) -> Result<Option<T>, ClientError>;
+
+    /// Saves a token, replacing any stored token.
+    fn save(&self, key: &StoreKey, token: &T) -> Result<(), ClientError>;
+
+    /// Deletes a token. Deleting a token which is not stored is not an error.
+    fn delete(&self, key: &StoreKey) -> Result<(), ClientError>;
+}
+
+impl<T, S: TokenStore<T> + ?Sized> TokenStore<T> for &S {
+    fn load(&self, key: &StoreKey) -> Result<Option<T>, ClientError> { (**self).load(key) }
+    fn save(&self, key: &StoreKey, token: &T) -> Result<(), ClientError> {
+        (**self).save(key, token)
+    }
+    fn delete(&self, key: &StoreKey) -> Result<(), ClientError> { (**self).delete(key) }
+}
+
+/// In-memory token storage.
+///
+/// Tokens are stored serialized, as they would be by a persistent store.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    tokens: Mutex<HashMap<StoreKey, String>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> TokenStore<T> for MemoryStore {
+    fn load(&self, key: &StoreKey) -> Result<Option<T>, ClientError> {
+        match self.tokens.lock().unwrap().get(key) {
+            Some(json) => Ok(Some(serde_json::from_str(json)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, key: &StoreKey, token: &T) -> Result<(), ClientError> {
+        let json = serde_json::to_string(token)?;
+        self.tokens.lock().unwrap().insert(key.clone(), json);
+        Ok(())
+    }
+
+    fn delete(&self, key: &StoreKey) -> Result<(), ClientError> {
+        self.tokens.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// JSON file token storage.
+///
+/// Each token is stored in its own file in a directory, named by a hash of its key. Files are
+/// replaced atomically by writing to a temporary file and renaming it, and on Unix are readable
+/// only by their owner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStore {
+    dir: PathBuf,
+    extension: &'static str,
+}
+
+impl FileStore {
+    /// Creates a store in a directory, which is created when the first token is saved.
+    pub fn new<D: Into<PathBuf>>(dir: D) -> Self {
+        FileStore::with_extension(dir.into(), "json")
+    }
+
+    pub(crate) fn with_extension(dir: PathBuf, extension: &'static str) -> Self {
+        FileStore { dir, extension }
+    }
+
+    /// Returns the directory.
+    pub fn dir(&self) -> &Path { &self.dir }
+
+    /// Returns the path of the file for a key.
+    pub fn path(&self, key: &StoreKey) -> PathBuf {
+        self.dir.join(format!("{}.{}", key.file_name(), self.extension))
+    }
+
+    /// Reads the contents of the file for a key, returning `None` if it does not exist.
+    pub(crate) fn read(&self, key: &StoreKey) -> Result<Option<Vec<u8>>, ClientError> {
+        let mut file = match File::open(self.path(key)) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(ClientError::from(err)),
+        };
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        Ok(Some(contents))
+    }
+
+    /// Atomically replaces the file for a key.
+    pub(crate) fn write(&self, key: &StoreKey, contents: &[u8]) -> Result<(), ClientError> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(key);
+        let temp = self.dir.join(format!(
+            ".{}.{}.{}.tmp",
+            key.file_name(),
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+        ));
+
+        let result = create_private(&temp)
+            .and_then(|mut file| {
+                file.write_all(contents)?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&temp, &path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        Ok(result?)
+    }
+
+    /// Removes the file for a key, if it exists.
+    pub(crate) fn remove(&self, key: &StoreKey) -> Result<(), ClientError> {
+        match fs::remove_file(self.path(key)) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => Ok(result?),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn create_private(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> io::Result<File> {
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+impl<T: Serialize + DeserializeOwned> TokenStore<T> for FileStore {
+    fn load(&self, key: &StoreKey) -> Result<Option<T>, ClientError> {
+        match self.read(key)? {
+            Some(json) => Ok(Some(serde_json::from_slice(&json)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, key: &StoreKey, token: &T) -> Result<(), ClientError> {
+        self.write(key, &serde_json::to_vec(token)?)
+    }
+
+    fn delete(&self, key: &StoreKey) -> Result<(), ClientError> {
+        self.remove(key)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    use client::{Client, ClientError};
+    use client::http::MemoryClient;
+    use client::response::FromResponse;
+    use provider::google::Installed;
+    use token::{OidcBearer, Refresh, Token};
+    use super::{FileStore, MemoryStore, StoreKey, StoredRefreshError, TokenStore};
+
+    /// Returns an empty temporary directory unique to the test.
+    pub fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("inth-oauth2-{}-{}", name, ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn token() -> OidcBearer<Refresh> {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":3600,"refresh_token":"bbbbbbbb"}"#
+            .parse()
+            .unwrap();
+        OidcBearer::from_response(&json).unwrap()
+    }
+
+    fn round_trip<S: TokenStore<OidcBearer<Refresh>>>(store: S) {
+        let key = StoreKey::new("https://example.com/token", "client", "user");
+        let other = StoreKey::new("https://example.com/token", "client", "other");
+
+        let token = token();
+
+        assert_eq!(None, store.load(&key).unwrap());
+        store.save(&key, &token).unwrap();
+        assert_eq!(Some(token), store.load(&key).unwrap());
+        assert_eq!(None, store.load(&other).unwrap());
+
+        store.delete(&key).unwrap();
+        assert_eq!(None, store.load(&key).unwrap());
+        store.delete(&key).unwrap();
+    }
+
+    #[test]
+    fn memory_store() {
+        round_trip(MemoryStore::new());
+    }
+
+    #[test]
+    fn file_store() {
+        let dir = temp_dir("file-store");
+        round_trip(FileStore::new(&dir));
+        assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_store_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("file-store-permissions");
+        let store = FileStore::new(&dir);
+        let key = StoreKey::new("https://example.com/token", "client", "user");
+        store.save(&key, &token()).unwrap();
+
+        let mode = fs::metadata(store.path(&key)).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn key_file_name() {
+        let a = StoreKey::new("ab", "c", "d");
+        let b = StoreKey::new("a", "bc", "d");
+        assert_ne!(a.file_name(), b.file_name());
+        assert_eq!(64, a.file_name().len());
+        assert!(FileStore::new("tokens").path(&a).to_str().unwrap().ends_with(".json"));
+    }
+
+    #[test]
+    fn ensure_token_stored() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"cccccccc","expires_in":3600,"refresh_token":"dddddddd"}"#);
+
+        let client = Client::new(Installed, String::from("client"), String::new(), None);
+        let store = MemoryStore::new();
+        let key = client.store_key("user");
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","expires_in":0,"refresh_token":"bbbbbbbb"}"#
+            .parse()
+            .unwrap();
+        let expired = OidcBearer::from_response(&json).unwrap();
+
+        let token = client.ensure_token_stored(&http, &store, &key, expired).unwrap();
+        assert_eq!("cccccccc", token.access_token());
+        assert_eq!(Some(token), store.load(&key).unwrap());
+    }
+
+    struct FailingStore;
+    impl TokenStore<OidcBearer<Refresh>> for FailingStore {
+        fn load(&self, _: &StoreKey) -> Result<Option<OidcBearer<Refresh>>, ClientError> {
+            Ok(None)
+        }
+        fn save(&self, _: &StoreKey, _: &OidcBearer<Refresh>) -> Result<(), ClientError> {
+            Err(ClientError::from(io::Error::other("disk full")))
+        }
+        fn delete(&self, _: &StoreKey) -> Result<(), ClientError> { Ok(()) }
+    }
+
+    #[test]
+    fn refresh_token_stored_save_error() {
+        let http = MemoryClient::new();
+        http.push_json(200, r#"{"token_type":"Bearer","access_token":"cccccccc","expires_in":3600,"refresh_token":"dddddddd"}"#);
+
+        let client = Client::new(Installed, String::from("client"), String::new(), None);
+        let key = client.store_key("user");
+
+        match client.refresh_token_stored(&http, &FailingStore, &key, token(), None) {
+            Err(StoredRefreshError::Save(token, ClientError::Io(_))) => {
+                assert_eq!("cccccccc", token.access_token());
+                assert_eq!("dddddddd", token.lifetime().refresh_token());
+            },
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn refresh_token_stored_refresh_error() {
+        let http = MemoryClient::new();
+        http.push_json(400, r#"{"error":"invalid_grant"}"#);
+
+        let client = Client::new(Installed, String::from("client"), String::new(), None);
+        let store = MemoryStore::new();
+        let key = client.store_key("user");
+
+        match client.refresh_token_stored(&http, &store, &key, token(), None) {
+            Err(err @ StoredRefreshError::Refresh(ClientError::OAuth2(_))) => {
+                assert!(err.into_token().is_none());
+            },
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(None::<OidcBearer<Refresh>>, store.load(&key).unwrap());
+    }
+}
@@ -0,0 +1,121 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{self, Map, Value};
+
+/// An authorization detail, describing fine-grained access requested or granted, as an
+/// alternative or complement to scope.
+///
+/// API-specific fields are kept in `fields`. Types representing a particular API's authorization
+/// details can be converted with `from_typed` and `to_typed`.
+///
+/// See [RFC 9396, section 2](https://tools.ietf.org/html/rfc9396#section-2).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationDetail {
+    /// Type of authorization detail, determining the other fields.
+    #[serde(rename = "type")]
+    pub detail_type: String,
+
+    /// Locations of the resources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<String>,
+
+    /// Kinds of actions to be taken at the resources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<String>,
+
+    /// Kinds of data requested from the resources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub datatypes: Vec<String>,
+
+    /// Identifier of a specific resource.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+
+    /// Types or levels of privilege.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub privileges: Vec<String>,
+
+    /// API-specific fields.
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+impl AuthorizationDetail {
+    /// Creates an authorization detail of a type.
+    pub fn new(detail_type: &str) -> Self {
+        AuthorizationDetail {
+            detail_type: detail_type.into(),
+            locations: Vec::new(),
+            actions: Vec::new(),
+            datatypes: Vec::new(),
+            identifier: None,
+            privileges: Vec::new(),
+            fields: Map::new(),
+        }
+    }
+
+    /// Adds an API-specific field.
+    pub fn field(mut self, name: &str, value: Value) -> Self {
+        self.fields.insert(name.into(), value);
+        self
+    }
+
+    /// Converts from a type serializing to an authorization detail object, including `type`.
+    pub fn from_typed<T: Serialize>(detail: &T) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(serde_json::to_value(detail)?)
+    }
+
+    /// Converts to a type deserializing from an authorization detail object.
+    pub fn to_typed<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_value(serde_json::to_value(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::AuthorizationDetail;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct PaymentInitiation {
+        #[serde(rename = "type")]
+        detail_type: String,
+        actions: Vec<String>,
+        #[serde(rename = "creditorName")]
+        creditor_name: String,
+    }
+
+    #[test]
+    fn serialize() {
+        let detail = AuthorizationDetail {
+            locations: vec![String::from("https://example.com/payments")],
+            actions: vec![String::from("initiate")],
+            ..AuthorizationDetail::new("payment_initiation")
+        }.field("instructedAmount", json!({"currency": "EUR", "amount": "123.50"}));
+        assert_eq!(
+            json!({
+                "type": "payment_initiation",
+                "locations": ["https://example.com/payments"],
+                "actions": ["initiate"],
+                "instructedAmount": {"currency": "EUR", "amount": "123.50"},
+            }),
+            serde_json::to_value(&detail).unwrap()
+        );
+        assert_eq!(detail, serde_json::from_value(serde_json::to_value(&detail).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn typed() {
+        let payment = PaymentInitiation {
+            detail_type: String::from("payment_initiation"),
+            actions: vec![String::from("initiate")],
+            creditor_name: String::from("Merchant A"),
+        };
+        let detail = AuthorizationDetail::from_typed(&payment).unwrap();
+        assert_eq!("payment_initiation", detail.detail_type);
+        assert_eq!(vec!["initiate"], detail.actions);
+        assert_eq!(Some(&json!("Merchant A")), detail.fields.get("creditorName"));
+        assert_eq!(payment, detail.to_typed().unwrap());
+    }
+}
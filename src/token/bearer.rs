@@ -1,21 +1,26 @@
 use serde_json::Value;
 
-use client::response::{FromResponse, ParseError};
-use token::{Token, Lifetime};
+use client::response::{FromResponse, ParseError, optional_array, optional_object};
+use secret::Secret;
+use token::{AuthorizationDetail, Confirmation, Token, Lifetime};
 
 /// The bearer token type.
 ///
 /// See [RFC 6750](http://tools.ietf.org/html/rfc6750).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Bearer<L: Lifetime> {
-    access_token: String,
+    access_token: Secret,
     scope: Option<String>,
     lifetime: L,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cnf: Option<Confirmation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    authorization_details: Vec<AuthorizationDetail>,
 }
 
 impl<L: Lifetime> Token<L> for Bearer<L> {
     fn access_token(&self) -> &str {
-        &self.access_token
+        self.access_token.secret()
     }
     fn scope(&self) -> Option<&str> {
         self.scope.as_ref().map(|s| &s[..])
@@ -23,28 +28,43 @@ impl<L: Lifetime> Token<L> for Bearer<L> {
     fn lifetime(&self) -> &L {
         &self.lifetime
     }
+    fn confirmation(&self) -> Option<&Confirmation> {
+        self.cnf.as_ref()
+    }
+    fn authorization_details(&self) -> &[AuthorizationDetail] {
+        &self.authorization_details
+    }
 }
 
 impl<L: Lifetime> Bearer<L> {
-    fn from_response_and_lifetime(json: &Value, lifetime: L) -> Result<Self, ParseError> {
+    /// Parses a token response with a token type, compared case-insensitively.
+    pub(crate) fn parse(
+        json: &Value,
+        lifetime: L,
+        token_type: &'static str,
+    ) -> Result<Self, ParseError> {
         let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
 
-        let token_type = obj.get("token_type")
+        let actual = obj.get("token_type")
             .and_then(Value::as_str)
             .ok_or(ParseError::ExpectedFieldType("token_type", "string"))?;
-        if token_type != "Bearer" && token_type != "bearer" {
-            return Err(ParseError::ExpectedFieldValue("token_type", "Bearer"));
+        if !actual.eq_ignore_ascii_case(token_type) {
+            return Err(ParseError::ExpectedFieldValue("token_type", token_type));
         }
 
         let access_token = obj.get("access_token")
             .and_then(Value::as_str)
             .ok_or(ParseError::ExpectedFieldType("access_token", "string"))?;
         let scope = obj.get("scope").and_then(Value::as_str);
+        let cnf = optional_object(obj, "cnf")?;
+        let authorization_details = optional_array(obj, "authorization_details")?;
 
         Ok(Bearer {
             access_token: access_token.into(),
             scope: scope.map(Into::into),
-            lifetime: lifetime,
+            lifetime,
+            cnf,
+            authorization_details,
         })
     }
 }
@@ -52,12 +72,12 @@ impl<L: Lifetime> Bearer<L> {
 impl<L: Lifetime> FromResponse for Bearer<L> {
     fn from_response(json: &Value) -> Result<Self, ParseError> {
         let lifetime = FromResponse::from_response(json)?;
-        Bearer::from_response_and_lifetime(json, lifetime)
+        Bearer::parse(json, lifetime, "Bearer")
     }
 
     fn from_response_inherit(json: &Value, prev: &Self) -> Result<Self, ParseError> {
         let lifetime = FromResponse::from_response_inherit(json, &prev.lifetime)?;
-        Bearer::from_response_and_lifetime(json, lifetime)
+        Bearer::parse(json, lifetime, "Bearer")
     }
 }
 
@@ -66,7 +86,8 @@ mod tests {
     use chrono::{Utc, Duration};
 
     use client::response::{FromResponse, ParseError};
-    use token::{Static, Refresh};
+    use secret::Secret;
+    use token::{Confirmation, Static, Refresh, Token};
     use super::Bearer;
 
     #[test]
@@ -83,9 +104,11 @@ mod tests {
         let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
         assert_eq!(
             Bearer {
-                access_token: String::from("aaaaaaaa"),
+                access_token: Secret::from("aaaaaaaa"),
                 scope: None,
                 lifetime: Static,
+                cnf: None,
+                authorization_details: Vec::new(),
             },
             Bearer::<Static>::from_response(&json).unwrap()
         );
@@ -96,9 +119,11 @@ mod tests {
         let json = r#"{"token_type":"bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
         assert_eq!(
             Bearer {
-                access_token: String::from("aaaaaaaa"),
+                access_token: Secret::from("aaaaaaaa"),
                 scope: None,
                 lifetime: Static,
+                cnf: None,
+                authorization_details: Vec::new(),
             },
             Bearer::<Static>::from_response(&json).unwrap()
         );
@@ -111,14 +136,43 @@ mod tests {
             .unwrap();
         assert_eq!(
             Bearer {
-                access_token: String::from("aaaaaaaa"),
+                access_token: Secret::from("aaaaaaaa"),
                 scope: Some(String::from("foo")),
                 lifetime: Static,
+                cnf: None,
+                authorization_details: Vec::new(),
             },
             Bearer::<Static>::from_response(&json).unwrap()
         );
     }
 
+    #[test]
+    fn from_response_with_authorization_details() {
+        let json = r#"
+            {
+                "token_type":"Bearer",
+                "access_token":"aaaaaaaa",
+                "authorization_details":[{"type":"payment_initiation","actions":["initiate"]}]
+            }
+        "#.parse().unwrap();
+        let bearer = Bearer::<Static>::from_response(&json).unwrap();
+        let details = bearer.authorization_details();
+        assert_eq!(1, details.len());
+        assert_eq!("payment_initiation", details[0].detail_type);
+        assert_eq!(vec!["initiate"], details[0].actions);
+    }
+
+    #[test]
+    fn from_response_with_invalid_authorization_details() {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","authorization_details":{}}"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("authorization_details", "array"),
+            Bearer::<Static>::from_response(&json).unwrap_err()
+        );
+    }
+
     #[test]
     fn from_response_refresh() {
         let json = r#"
@@ -130,7 +184,7 @@ mod tests {
             }
         "#.parse().unwrap();
         let bearer = Bearer::<Refresh>::from_response(&json).unwrap();
-        assert_eq!("aaaaaaaa", bearer.access_token);
+        assert_eq!("aaaaaaaa", bearer.access_token.secret());
         assert_eq!(None, bearer.scope);
         let refresh = bearer.lifetime;
         assert_eq!("bbbbbbbb", refresh.refresh_token());
@@ -158,11 +212,47 @@ mod tests {
             }
         "#.parse().unwrap();
         let bearer = Bearer::<Refresh>::from_response_inherit(&json, &prev).unwrap();
-        assert_eq!("cccccccc", bearer.access_token);
+        assert_eq!("cccccccc", bearer.access_token.secret());
         assert_eq!(None, bearer.scope);
         let refresh = bearer.lifetime;
         assert_eq!("bbbbbbbb", refresh.refresh_token());
         assert!(refresh.expires() > &Utc::now());
         assert!(refresh.expires() <= &(Utc::now() + Duration::seconds(3600)));
     }
+
+    #[test]
+    fn from_response_with_cnf() {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","cnf":{"x5t#S256":"bbbb"}}"#
+            .parse()
+            .unwrap();
+        let bearer = Bearer::<Static>::from_response(&json).unwrap();
+        assert_eq!(
+            Some(&Confirmation { x5t_s256: Some(String::from("bbbb")), jkt: None }),
+            bearer.confirmation()
+        );
+
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","cnf":"bbbb"}"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("cnf", "object"),
+            Bearer::<Static>::from_response(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn debug_redacted() {
+        let json = r#"
+            {
+                "token_type":"Bearer",
+                "access_token":"aaaaaaaa",
+                "expires_in":3600,
+                "refresh_token":"bbbbbbbb"
+            }
+        "#.parse().unwrap();
+        let bearer = Bearer::<Refresh>::from_response(&json).unwrap();
+        let debug = format!("{:?}", bearer);
+        assert!(!debug.contains("aaaaaaaa"));
+        assert!(!debug.contains("bbbbbbbb"));
+    }
 }
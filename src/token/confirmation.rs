@@ -0,0 +1,68 @@
+use client::mtls::ClientCertificate;
+use dpop::DpopKey;
+
+/// Confirmation of the key a token is bound to, from the `cnf` member of a token.
+///
+/// Resources check that requests using a bound token are made with the same key.
+///
+/// See [RFC 7800, section 3.1](https://tools.ietf.org/html/rfc7800#section-3.1).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Confirmation {
+    /// SHA-256 thumbprint of the client certificate the token is bound to.
+    ///
+    /// See [RFC 8705, section 3.1](https://tools.ietf.org/html/rfc8705#section-3.1).
+    #[serde(rename = "x5t#S256", default, skip_serializing_if = "Option::is_none")]
+    pub x5t_s256: Option<String>,
+
+    /// JWK thumbprint of the DPoP key the token is bound to.
+    ///
+    /// See [RFC 9449, section 6.1](https://tools.ietf.org/html/rfc9449#section-6.1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jkt: Option<String>,
+}
+
+impl Confirmation {
+    /// Returns true if the token is bound to the certificate.
+    pub fn matches_certificate(&self, cert: &ClientCertificate) -> bool {
+        self.x5t_s256.as_ref().map(|s| &s[..]) == Some(cert.thumbprint())
+    }
+
+    /// Returns true if the token is bound to the DPoP key.
+    pub fn matches_dpop_key(&self, key: &DpopKey) -> bool {
+        self.jkt.as_ref().map(|s| &s[..]) == Some(key.thumbprint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use client::mtls::tests::client_certificate;
+    use dpop::DpopKey;
+    use super::Confirmation;
+
+    #[test]
+    fn matches_certificate() {
+        let cert = client_certificate();
+        let cnf = Confirmation { x5t_s256: Some(cert.thumbprint().into()), jkt: None };
+        assert!(cnf.matches_certificate(&cert));
+        assert!(!cnf.matches_certificate(&client_certificate()));
+        assert!(!Confirmation::default().matches_certificate(&cert));
+    }
+
+    #[test]
+    fn matches_dpop_key() {
+        let key = DpopKey::generate().unwrap();
+        let cnf = Confirmation { x5t_s256: None, jkt: Some(key.thumbprint().into()) };
+        assert!(cnf.matches_dpop_key(&key));
+        assert!(!cnf.matches_dpop_key(&DpopKey::generate().unwrap()));
+    }
+
+    #[test]
+    fn serialize() {
+        let cnf = Confirmation { x5t_s256: Some(String::from("aaaa")), jkt: None };
+        let json = serde_json::to_string(&cnf).unwrap();
+        assert_eq!(r#"{"x5t#S256":"aaaa"}"#, json);
+        assert_eq!(cnf, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,86 @@
+use serde_json::Value;
+
+use client::response::{FromResponse, ParseError};
+use token::{AuthorizationDetail, Bearer, Confirmation, Lifetime, Token};
+
+/// The DPoP token type.
+///
+/// Requests to resources must be authorized with a proof from the key the token is bound to,
+/// using `DpopKey::authorize`.
+///
+/// Serializes compatibly with `Bearer`.
+///
+/// See [RFC 9449, section 5](https://tools.ietf.org/html/rfc9449#section-5).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Dpop<L: Lifetime> {
+    bearer: Bearer<L>,
+}
+
+impl<L: Lifetime> Token<L> for Dpop<L> {
+    fn access_token(&self) -> &str { self.bearer.access_token() }
+    fn scope(&self) -> Option<&str> { self.bearer.scope() }
+    fn lifetime(&self) -> &L { self.bearer.lifetime() }
+    fn confirmation(&self) -> Option<&Confirmation> { self.bearer.confirmation() }
+    fn authorization_details(&self) -> &[AuthorizationDetail] {
+        self.bearer.authorization_details()
+    }
+}
+
+impl<L: Lifetime> FromResponse for Dpop<L> {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        let lifetime = FromResponse::from_response(json)?;
+        Ok(Dpop { bearer: Bearer::parse(json, lifetime, "DPoP")? })
+    }
+
+    fn from_response_inherit(json: &Value, prev: &Self) -> Result<Self, ParseError> {
+        let lifetime = FromResponse::from_response_inherit(json, prev.bearer.lifetime())?;
+        Ok(Dpop { bearer: Bearer::parse(json, lifetime, "DPoP")? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use client::response::{FromResponse, ParseError};
+    use token::{Refresh, Static, Token};
+    use super::Dpop;
+
+    #[test]
+    fn from_response() {
+        let json = r#"{"token_type":"DPoP","access_token":"aaaaaaaa","cnf":{"jkt":"bbbb"}}"#
+            .parse()
+            .unwrap();
+        let token = Dpop::<Static>::from_response(&json).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!(Some("bbbb"), token.confirmation().unwrap().jkt.as_ref().map(|s| &s[..]));
+    }
+
+    #[test]
+    fn from_response_bearer() {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldValue("token_type", "DPoP"),
+            Dpop::<Static>::from_response(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn from_response_inherit() {
+        let json = r#"
+            {
+                "token_type":"DPoP",
+                "access_token":"aaaaaaaa",
+                "expires_in":3600,
+                "refresh_token":"bbbbbbbb"
+            }
+        "#.parse().unwrap();
+        let prev = Dpop::<Refresh>::from_response(&json).unwrap();
+
+        let json = r#"{"token_type":"dpop","access_token":"cccccccc","expires_in":3600}"#
+            .parse()
+            .unwrap();
+        let token = Dpop::<Refresh>::from_response_inherit(&json, &prev).unwrap();
+        assert_eq!("cccccccc", token.access_token());
+        assert_eq!("bbbbbbbb", token.lifetime().refresh_token());
+    }
+}
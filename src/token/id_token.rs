@@ -0,0 +1,345 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error as DeError;
+use serde_json::{Map, Value};
+
+use client::response::{
+    ParseError,
+    optional_string,
+    optional_timestamp,
+    string_or_array,
+};
+use jwt::{Jwks, Jwt, JwtError};
+
+/// OpenID Connect ID token claims.
+///
+/// See [OpenID Connect Core 1.0, section
+/// 2](https://openid.net/specs/openid-connect-core-1_0.html#IDToken).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdTokenClaims {
+    /// Issuer identifier.
+    pub iss: String,
+
+    /// Subject identifier.
+    pub sub: String,
+
+    /// Audiences the token is intended for.
+    pub aud: Vec<String>,
+
+    /// Expiry time.
+    pub exp: DateTime<Utc>,
+
+    /// Time the token was issued.
+    pub iat: DateTime<Utc>,
+
+    /// Time the user authenticated.
+    pub auth_time: Option<DateTime<Utc>>,
+
+    /// Nonce from the authorization request.
+    pub nonce: Option<String>,
+
+    /// Authorized party.
+    pub azp: Option<String>,
+
+    /// Additional claims, such as `email`.
+    pub extra: Map<String, Value>,
+}
+
+const CLAIMS: &[&str] = &["iss", "sub", "aud", "exp", "iat", "auth_time", "nonce", "azp"];
+
+impl IdTokenClaims {
+    fn from_claims(obj: &Map<String, Value>) -> Result<Self, ParseError> {
+        let extra = obj.iter()
+            .filter(|&(key, _)| !CLAIMS.contains(&&key[..]))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        Ok(IdTokenClaims {
+            iss: optional_string(obj, "iss")?
+                .ok_or(ParseError::ExpectedFieldType("iss", "string"))?,
+            sub: optional_string(obj, "sub")?
+                .ok_or(ParseError::ExpectedFieldType("sub", "string"))?,
+            aud: string_or_array(obj, "aud")?,
+            exp: optional_timestamp(obj, "exp")?
+                .ok_or(ParseError::ExpectedFieldType("exp", "i64"))?,
+            iat: optional_timestamp(obj, "iat")?
+                .ok_or(ParseError::ExpectedFieldType("iat", "i64"))?,
+            auth_time: optional_timestamp(obj, "auth_time")?,
+            nonce: optional_string(obj, "nonce")?,
+            azp: optional_string(obj, "azp")?,
+            extra,
+        })
+    }
+}
+
+/// ID token validation parameters.
+///
+/// See [OpenID Connect Core 1.0, section
+/// 3.1.3.7](https://openid.net/specs/openid-connect-core-1_0.html#IDTokenValidation).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdTokenValidation {
+    issuer: String,
+    client_id: String,
+    nonce: Option<String>,
+    leeway: Duration,
+}
+
+impl IdTokenValidation {
+    /// Creates validation parameters for tokens from an issuer to a client.
+    pub fn new(issuer: String, client_id: String) -> Self {
+        IdTokenValidation {
+            issuer,
+            client_id,
+            nonce: None,
+            leeway: Duration::seconds(60),
+        }
+    }
+
+    /// Requires the `nonce` claim to equal the nonce sent in the authorization request.
+    pub fn nonce(mut self, nonce: String) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Sets the allowed clock skew for `exp` and `iat`. Defaults to 60 seconds.
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+}
+
+/// OpenID Connect ID token.
+///
+/// Claims are parsed but not verified until `validate` is called.
+#[derive(Clone, PartialEq)]
+pub struct IdToken {
+    raw: String,
+    jwt: Jwt,
+    claims: IdTokenClaims,
+}
+
+impl IdToken {
+    /// Parses an ID token without validating it.
+    pub fn parse(raw: &str) -> Result<Self, ParseError> {
+        let jwt = Jwt::decode(raw).map_err(|_| ParseError::ExpectedFieldType("id_token", "JWT"))?;
+        let claims = IdTokenClaims::from_claims(jwt.claims())?;
+        Ok(IdToken { raw: raw.into(), jwt, claims })
+    }
+
+    /// Returns the encoded ID token.
+    pub fn as_str(&self) -> &str { &self.raw }
+
+    /// Returns the unverified claims.
+    pub fn claims(&self) -> &IdTokenClaims { &self.claims }
+
+    /// Validates the signature against a key set, then the `iss`, `aud`, `azp`, `exp`, `iat`
+    /// and `nonce` claims, returning the claims if valid.
+    pub fn validate(
+        &self,
+        jwks: &Jwks,
+        validation: &IdTokenValidation,
+    ) -> Result<&IdTokenClaims, JwtError> {
+        self.jwt.verify(jwks)?;
+
+        let claims = &self.claims;
+        let now = Utc::now();
+
+        if claims.iss != validation.issuer {
+            return Err(JwtError::InvalidClaim("iss"));
+        }
+        if !claims.aud.contains(&validation.client_id) {
+            return Err(JwtError::InvalidClaim("aud"));
+        }
+        let azp_required = claims.aud.len() > 1 || claims.azp.is_some();
+        if azp_required && claims.azp.as_ref() != Some(&validation.client_id) {
+            return Err(JwtError::InvalidClaim("azp"));
+        }
+        if claims.exp + validation.leeway < now {
+            return Err(JwtError::InvalidClaim("exp"));
+        }
+        if claims.iat - validation.leeway > now {
+            return Err(JwtError::InvalidClaim("iat"));
+        }
+        if validation.nonce.is_some() && claims.nonce != validation.nonce {
+            return Err(JwtError::InvalidClaim("nonce"));
+        }
+
+        Ok(claims)
+    }
+}
+
+impl fmt::Debug for IdToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("IdToken").field("claims", &self.claims).finish()
+    }
+}
+
+impl Serialize for IdToken {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for IdToken {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        IdToken::parse(&raw).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use serde_json::Value;
+
+    use client::response::ParseError;
+    use jwt::{Jwks, JwtError};
+    use jwt::tests::{ec_key, jwk, rsa_key, sign};
+    use super::{IdToken, IdTokenValidation};
+
+    fn claims() -> Value {
+        let now = Utc::now().timestamp();
+        json!({
+            "iss": "https://accounts.example.com",
+            "sub": "110169484474386276334",
+            "aud": "client",
+            "exp": now + 3600,
+            "iat": now,
+            "nonce": "n-0S6_WzA2Mj",
+            "email": "user@example.com",
+        })
+    }
+
+    fn validation() -> IdTokenValidation {
+        IdTokenValidation::new(
+            String::from("https://accounts.example.com"),
+            String::from("client"),
+        )
+    }
+
+    #[test]
+    fn parse() {
+        let id_token = IdToken::parse(&sign(&ec_key(), "1", &claims())).unwrap();
+        let claims = id_token.claims();
+        assert_eq!("https://accounts.example.com", claims.iss);
+        assert_eq!("110169484474386276334", claims.sub);
+        assert_eq!(vec![String::from("client")], claims.aud);
+        assert_eq!(Some(String::from("n-0S6_WzA2Mj")), claims.nonce);
+        assert_eq!(Some(&json!("user@example.com")), claims.extra.get("email"));
+    }
+
+    #[test]
+    fn parse_not_jwt() {
+        assert_eq!(
+            ParseError::ExpectedFieldType("id_token", "JWT"),
+            IdToken::parse("aaaaaaaa").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn parse_without_sub() {
+        let mut claims = claims();
+        claims.as_object_mut().unwrap().remove("sub");
+        assert_eq!(
+            ParseError::ExpectedFieldType("sub", "string"),
+            IdToken::parse(&sign(&ec_key(), "1", &claims)).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn validate_rs256() {
+        let key = rsa_key();
+        let jwks = Jwks { keys: vec![jwk(&key, "1")] };
+        let id_token = IdToken::parse(&sign(&key, "1", &claims())).unwrap();
+        let validation = validation().nonce(String::from("n-0S6_WzA2Mj"));
+        assert_eq!("110169484474386276334", id_token.validate(&jwks, &validation).unwrap().sub);
+    }
+
+    #[test]
+    fn validate_es256() {
+        let key = ec_key();
+        let jwks = Jwks { keys: vec![jwk(&key, "1")] };
+        let id_token = IdToken::parse(&sign(&key, "1", &claims())).unwrap();
+        id_token.validate(&jwks, &validation()).unwrap();
+    }
+
+    fn validate_claim(claims: Value, validation: IdTokenValidation) -> JwtError {
+        let key = ec_key();
+        let jwks = Jwks { keys: vec![jwk(&key, "1")] };
+        let id_token = IdToken::parse(&sign(&key, "1", &claims)).unwrap();
+        id_token.validate(&jwks, &validation).unwrap_err()
+    }
+
+    #[test]
+    fn validate_iss() {
+        let validation = IdTokenValidation::new(
+            String::from("https://evil.example.com"),
+            String::from("client"),
+        );
+        match validate_claim(claims(), validation) {
+            JwtError::InvalidClaim("iss") => {},
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn validate_aud() {
+        let mut claims = claims();
+        claims["aud"] = json!("other");
+        match validate_claim(claims, validation()) {
+            JwtError::InvalidClaim("aud") => {},
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn validate_aud_without_client() {
+        let mut claims = claims();
+        claims["aud"] = json!(["other", "another"]);
+        claims["azp"] = json!("client");
+        match validate_claim(claims, validation()) {
+            JwtError::InvalidClaim("aud") => {},
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn validate_azp() {
+        let mut claims = claims();
+        claims["aud"] = json!(["other", "client"]);
+        match validate_claim(claims, validation()) {
+            JwtError::InvalidClaim("azp") => {},
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn validate_exp() {
+        let mut claims = claims();
+        claims["exp"] = json!((Utc::now() - Duration::minutes(5)).timestamp());
+        match validate_claim(claims, validation()) {
+            JwtError::InvalidClaim("exp") => {},
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn validate_iat() {
+        let mut claims = claims();
+        claims["iat"] = json!((Utc::now() + Duration::minutes(5)).timestamp());
+        match validate_claim(claims, validation()) {
+            JwtError::InvalidClaim("iat") => {},
+            err => panic!("{:?}", err),
+        }
+    }
+
+    #[test]
+    fn validate_nonce() {
+        match validate_claim(claims(), validation().nonce(String::from("other"))) {
+            JwtError::InvalidClaim("nonce") => {},
+            err => panic!("{:?}", err),
+        }
+    }
+}
@@ -5,17 +5,28 @@
 //!
 //! Expiring and non-expiring tokens are abstracted through the `Lifetime` trait.
 
+mod authorization_detail;
 mod bearer;
+mod confirmation;
+mod dpop;
 mod expiring;
+mod id_token;
+mod oidc;
 mod refresh;
 mod statik;
 
+pub use self::authorization_detail::AuthorizationDetail;
 pub use self::bearer::Bearer;
+pub use self::confirmation::Confirmation;
+pub use self::dpop::Dpop;
 pub use self::expiring::Expiring;
+pub use self::id_token::{IdToken, IdTokenClaims, IdTokenValidation};
+pub use self::oidc::OidcBearer;
 pub use self::refresh::Refresh;
 pub use self::statik::Static;
 
 use client::response::FromResponse;
+use scope::Scope;
 
 /// OAuth 2.0 tokens.
 ///
@@ -27,10 +38,25 @@ pub trait Token<L: Lifetime>: FromResponse {
     fn access_token(&self) -> &str;
 
     /// Returns the scope, if available.
+    ///
+    /// Use `Client::granted_scope` to parse it with the provider's delimiter.
     fn scope(&self) -> Option<&str>;
 
+    /// Returns the scope granted, if available, parsed with the RFC 6749 space delimiter.
+    ///
+    /// Use `Client::granted_scope` for providers with other delimiters.
+    fn granted_scope(&self) -> Option<Scope> { self.scope().map(Scope::from) }
+
     /// Returns the token lifetime.
     fn lifetime(&self) -> &L;
+
+    /// Returns the confirmation of the key the token is bound to, if any.
+    fn confirmation(&self) -> Option<&Confirmation> { None }
+
+    /// Returns the authorization details granted, if any.
+    ///
+    /// See [RFC 9396, section 7](https://tools.ietf.org/html/rfc9396#section-7).
+    fn authorization_details(&self) -> &[AuthorizationDetail] { &[] }
 }
 
 /// OAuth 2.0 token lifetimes.
@@ -38,3 +64,25 @@ pub trait Lifetime: FromResponse {
     /// Returns true if the access token is no longer valid.
     fn expired(&self) -> bool;
 }
+
+/// Token type hints.
+///
+/// See [RFC 7009, section 2.1](https://tools.ietf.org/html/rfc7009#section-2.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenTypeHint {
+    /// An access token.
+    AccessToken,
+
+    /// A refresh token.
+    RefreshToken,
+}
+
+impl TokenTypeHint {
+    /// Returns the `token_type_hint` parameter value.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TokenTypeHint::AccessToken => "access_token",
+            TokenTypeHint::RefreshToken => "refresh_token",
+        }
+    }
+}
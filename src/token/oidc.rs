@@ -0,0 +1,158 @@
+use serde_json::Value;
+
+use client::response::{FromResponse, ParseError};
+use token::{AuthorizationDetail, Bearer, Confirmation, IdToken, Lifetime, Token};
+
+/// A bearer token with an optional OpenID Connect ID token.
+///
+/// Serializes compatibly with `Bearer`.
+///
+/// See [OpenID Connect Core 1.0, section
+/// 3.1.3.3](https://openid.net/specs/openid-connect-core-1_0.html#TokenResponse).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OidcBearer<L: Lifetime> {
+    #[serde(flatten)]
+    bearer: Bearer<L>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id_token: Option<IdToken>,
+}
+
+impl<L: Lifetime> OidcBearer<L> {
+    /// Returns the ID token, if the `openid` scope was requested.
+    pub fn id_token(&self) -> Option<&IdToken> { self.id_token.as_ref() }
+}
+
+impl<L: Lifetime> Token<L> for OidcBearer<L> {
+    fn access_token(&self) -> &str { self.bearer.access_token() }
+    fn scope(&self) -> Option<&str> { self.bearer.scope() }
+    fn lifetime(&self) -> &L { self.bearer.lifetime() }
+    fn confirmation(&self) -> Option<&Confirmation> { self.bearer.confirmation() }
+    fn authorization_details(&self) -> &[AuthorizationDetail] {
+        self.bearer.authorization_details()
+    }
+}
+
+fn id_token(json: &Value) -> Result<Option<IdToken>, ParseError> {
+    let obj = json.as_object().ok_or(ParseError::ExpectedType("object"))?;
+    match obj.get("id_token") {
+        None => Ok(None),
+        Some(id_token) => {
+            let id_token = id_token.as_str()
+                .ok_or(ParseError::ExpectedFieldType("id_token", "string"))?;
+            IdToken::parse(id_token).map(Some)
+        },
+    }
+}
+
+impl<L: Lifetime> FromResponse for OidcBearer<L> {
+    fn from_response(json: &Value) -> Result<Self, ParseError> {
+        Ok(OidcBearer {
+            bearer: Bearer::from_response(json)?,
+            id_token: id_token(json)?,
+        })
+    }
+
+    /// ID tokens are not always returned when refreshing, in which case the previous ID token is
+    /// kept.
+    fn from_response_inherit(json: &Value, prev: &Self) -> Result<Self, ParseError> {
+        Ok(OidcBearer {
+            bearer: Bearer::from_response_inherit(json, &prev.bearer)?,
+            id_token: id_token(json)?.or_else(|| prev.id_token.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json;
+
+    use client::response::{FromResponse, ParseError};
+    use jwt::tests::{ec_key, sign};
+    use token::{Bearer, Expiring, Refresh, Static, Token};
+    use super::OidcBearer;
+
+    fn id_token() -> String {
+        let now = Utc::now().timestamp();
+        sign(&ec_key(), "1", &json!({
+            "iss": "https://accounts.example.com",
+            "sub": "1234",
+            "aud": "client",
+            "exp": now + 3600,
+            "iat": now,
+        }))
+    }
+
+    #[test]
+    fn from_response() {
+        let json = json!({
+            "token_type": "Bearer",
+            "access_token": "aaaaaaaa",
+            "expires_in": 3600,
+            "id_token": id_token(),
+        });
+        let token = OidcBearer::<Expiring>::from_response(&json).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert_eq!("1234", token.id_token().unwrap().claims().sub);
+    }
+
+    #[test]
+    fn from_response_without_id_token() {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
+        let token = OidcBearer::<Static>::from_response(&json).unwrap();
+        assert!(token.id_token().is_none());
+    }
+
+    #[test]
+    fn from_response_invalid_id_token() {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa","id_token":"bbbbbbbb"}"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            ParseError::ExpectedFieldType("id_token", "JWT"),
+            OidcBearer::<Static>::from_response(&json).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn from_response_inherit() {
+        let json = json!({
+            "token_type": "Bearer",
+            "access_token": "aaaaaaaa",
+            "expires_in": 3600,
+            "refresh_token": "bbbbbbbb",
+            "id_token": id_token(),
+        });
+        let prev = OidcBearer::<Refresh>::from_response(&json).unwrap();
+
+        let json = r#"{"token_type":"Bearer","access_token":"cccccccc","expires_in":3600}"#
+            .parse()
+            .unwrap();
+        let token = OidcBearer::<Refresh>::from_response_inherit(&json, &prev).unwrap();
+        assert_eq!("cccccccc", token.access_token());
+        assert_eq!(prev.id_token(), token.id_token());
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let json = json!({
+            "token_type": "Bearer",
+            "access_token": "aaaaaaaa",
+            "expires_in": 3600,
+            "id_token": id_token(),
+        });
+        let token = OidcBearer::<Expiring>::from_response(&json).unwrap();
+        let serialized = serde_json::to_string(&token).unwrap();
+        assert_eq!(token, serde_json::from_str(&serialized).unwrap());
+    }
+
+    #[test]
+    fn deserialize_bearer() {
+        let json = r#"{"token_type":"Bearer","access_token":"aaaaaaaa"}"#.parse().unwrap();
+        let bearer = Bearer::<Static>::from_response(&json).unwrap();
+        let serialized = serde_json::to_string(&bearer).unwrap();
+        let token: OidcBearer<Static> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!("aaaaaaaa", token.access_token());
+        assert!(token.id_token().is_none());
+    }
+}
@@ -2,12 +2,13 @@ use chrono::{DateTime, Utc, Duration};
 use serde_json::Value;
 
 use client::response::{FromResponse, ParseError};
+use secret::Secret;
 use token::Lifetime;
 
 /// An expiring token which can be refreshed.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Refresh {
-    refresh_token: String,
+    refresh_token: Secret,
     expires: DateTime<Utc>,
 }
 
@@ -15,7 +16,7 @@ impl Refresh {
     /// Returns the refresh token.
     ///
     /// See [RFC 6749, section 1.5](http://tools.ietf.org/html/rfc6749#section-1.5).
-    pub fn refresh_token(&self) -> &str { &self.refresh_token }
+    pub fn refresh_token(&self) -> &str { self.refresh_token.secret() }
 
     /// Returns the expiry time of the access token.
     pub fn expires(&self) -> &DateTime<Utc> { &self.expires }
@@ -47,7 +48,7 @@ impl FromResponse for Refresh {
 
         let refresh_token = obj.get("refresh_token")
             .and_then(Value::as_str)
-            .or(Some(&prev.refresh_token))
+            .or(Some(prev.refresh_token.secret()))
             .ok_or(ParseError::ExpectedFieldType("refresh_token", "string"))?;
 
         let expires_in = obj.get("expires_in")
@@ -66,13 +67,14 @@ mod tests {
     use chrono::{Utc, Duration};
 
     use client::response::FromResponse;
+    use secret::Secret;
     use super::Refresh;
 
     #[test]
     fn from_response() {
         let json = r#"{"refresh_token":"aaaaaaaa","expires_in":3600}"#.parse().unwrap();
         let refresh = Refresh::from_response(&json).unwrap();
-        assert_eq!("aaaaaaaa", refresh.refresh_token);
+        assert_eq!("aaaaaaaa", refresh.refresh_token());
         assert!(refresh.expires > Utc::now());
         assert!(refresh.expires <= Utc::now() + Duration::seconds(3600));
     }
@@ -81,11 +83,11 @@ mod tests {
     fn from_response_inherit() {
         let json = r#"{"expires_in":3600}"#.parse().unwrap();
         let prev = Refresh {
-            refresh_token: String::from("aaaaaaaa"),
+            refresh_token: Secret::from("aaaaaaaa"),
             expires: Utc::now(),
         };
         let refresh = Refresh::from_response_inherit(&json, &prev).unwrap();
-        assert_eq!("aaaaaaaa", refresh.refresh_token);
+        assert_eq!("aaaaaaaa", refresh.refresh_token());
         assert!(refresh.expires > Utc::now());
         assert!(refresh.expires <= Utc::now() + Duration::seconds(3600));
     }